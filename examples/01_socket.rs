@@ -3,13 +3,18 @@ extern crate io_bluetooth;
 use std::io;
 use std::iter;
 
-use io_bluetooth::bt::{self, BtStream};
+use io_bluetooth::bt::{self, BtSocketAddr, BtStream};
 
 fn main() -> io::Result<()> {
     let devices = bt::discover_devices()?;
     println!("Devices:");
     for (idx, device) in devices.iter().enumerate() {
-        println!("{}: {}", idx, *device);
+        println!(
+            "{}: {} ({:?})",
+            idx,
+            device.addr(),
+            device.major_device_class()
+        );
     }
 
     if devices.len() == 0 {
@@ -21,7 +26,8 @@ fn main() -> io::Result<()> {
 
     let device_idx = request_device_idx(devices.len())?;
 
-    let socket = BtStream::connect(iter::once(&devices[device_idx]), bt::BtProtocol::RFCOMM)?;
+    let addr = BtSocketAddr::rfcomm(*devices[device_idx].addr(), 1);
+    let socket = BtStream::connect(iter::once(&addr), bt::BtProtocol::RFCOMM)?;
 
     match socket.peer_addr() {
         Ok(name) => println!("Peername: {}.", name.to_string()),
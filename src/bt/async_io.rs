@@ -0,0 +1,310 @@
+use std::io;
+use std::net::Shutdown;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use async_io::{Async, IoSafe};
+use futures_io::{AsyncRead, AsyncWrite};
+
+use super::split;
+use super::{
+    BtListener, BtOwnedReadHalf, BtOwnedWriteHalf, BtProtocol, BtReadHalf, BtSocketAddr, BtStream,
+    BtWriteHalf,
+};
+use crate::sys::bt::btc as c;
+use crate::sys_common::bt as bt_imp;
+
+/// A Bluetooth socket server, asynchronously listening for connections.
+///
+/// This is the [async-io] based counterpart of [`BtListener`], usable from any executor.
+///
+/// [async-io]: https://docs.rs/async-io
+/// [`BtListener`]: struct.BtListener.html
+#[derive(Debug)]
+pub struct AsyncBtListener {
+    inner: Async<BtListener>,
+}
+
+/// An asynchronous Bluetooth stream between a local and remote socket.
+///
+/// This is the [async-io] based counterpart of [`BtStream`], usable from any executor.
+/// Data is transmitted through its [`AsyncRead`] and [`AsyncWrite`] implementations, or
+/// with [`recv`] and [`send`]. The traits are also implemented for `&AsyncBtStream` so
+/// that reading and writing can happen concurrently.
+///
+/// [async-io]: https://docs.rs/async-io
+/// [`BtStream`]: struct.BtStream.html
+/// [`AsyncRead`]: https://docs.rs/futures-io/0.3/futures_io/trait.AsyncRead.html
+/// [`AsyncWrite`]: https://docs.rs/futures-io/0.3/futures_io/trait.AsyncWrite.html
+/// [`recv`]: #method.recv
+/// [`send`]: #method.send
+#[derive(Debug)]
+pub struct AsyncBtStream {
+    inner: Async<BtStream>,
+}
+
+impl AsyncBtListener {
+    /// Creates a new `AsyncBtListener` which will be bound to the specified address.
+    ///
+    /// See [`BtListener::bind`] for details.
+    ///
+    /// [`BtListener::bind`]: struct.BtListener.html#method.bind
+    pub fn bind<'a, I>(addrs: I, protocol: BtProtocol) -> io::Result<Self>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        Self::from_std(BtListener::bind(addrs, protocol)?)
+    }
+
+    /// Creates a new `AsyncBtListener` from a blocking [`BtListener`], putting it into
+    /// nonblocking mode.
+    ///
+    /// [`BtListener`]: struct.BtListener.html
+    pub fn from_std(listener: BtListener) -> io::Result<Self> {
+        Ok(Self {
+            inner: Async::new(listener)?,
+        })
+    }
+
+    /// Turns this listener back into a blocking [`BtListener`].
+    ///
+    /// [`BtListener`]: struct.BtListener.html
+    pub fn into_std(self) -> io::Result<BtListener> {
+        let listener = self.inner.into_inner()?;
+        listener.set_nonblocking(false)?;
+        Ok(listener)
+    }
+
+    /// Accepts a new incoming connection from this listener.
+    ///
+    /// The returned future resolves once a remote device has connected, with the
+    /// corresponding [`AsyncBtStream`] and the remote peer's address.
+    ///
+    /// [`AsyncBtStream`]: struct.AsyncBtStream.html
+    pub async fn accept(&self) -> io::Result<(AsyncBtStream, BtSocketAddr)> {
+        let (stream, addr) = self.inner.read_with(|listener| listener.accept()).await?;
+        Ok((AsyncBtStream::from_std(stream)?, addr))
+    }
+
+    /// Returns the local socket address of this listener.
+    pub fn local_addr(&self) -> io::Result<BtSocketAddr> {
+        self.inner.get_ref().local_addr()
+    }
+
+    /// Returns the socket protocol of this socket.
+    pub fn protocol(&self) -> BtProtocol {
+        self.inner.get_ref().protocol()
+    }
+}
+
+impl AsyncBtStream {
+    /// Opens a Bluetooth connection to a remote host.
+    ///
+    /// If `addrs` yields multiple addresses, `connect` will be attempted with each of the
+    /// addresses until the connection succeeds. If none of the addresses succeed, the
+    /// error returned from the last connection attempt (the last address) is returned.
+    ///
+    /// The connection attempt is not limited in time; race the returned future against
+    /// a timer to limit it.
+    pub async fn connect<'a, I>(addrs: I, protocol: BtProtocol) -> io::Result<Self>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        let mut last_err = None;
+        for addr in addrs {
+            match Self::connect_addr(addr, protocol).await {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any addresses",
+            )
+        }))
+    }
+
+    async fn connect_addr(addr: &BtSocketAddr, protocol: BtProtocol) -> io::Result<Self> {
+        let (stream, connected) =
+            bt_imp::BtStream::connect_nonblocking(addr, protocol, c::SOCK_STREAM)?;
+        let inner = Async::new(BtStream(stream))?;
+
+        if !connected {
+            // The socket becomes writable once the connection attempt completes, whether
+            // it succeeded or not.
+            inner.writable().await?;
+            if let Some(e) = inner.get_ref().take_error()? {
+                return Err(e);
+            }
+        }
+        Ok(Self { inner })
+    }
+
+    /// Creates a new `AsyncBtStream` from a blocking [`BtStream`], putting it into
+    /// nonblocking mode.
+    ///
+    /// [`BtStream`]: struct.BtStream.html
+    pub fn from_std(stream: BtStream) -> io::Result<Self> {
+        Ok(Self {
+            inner: Async::new(stream)?,
+        })
+    }
+
+    /// Turns this stream back into a blocking [`BtStream`].
+    ///
+    /// [`BtStream`]: struct.BtStream.html
+    pub fn into_std(self) -> io::Result<BtStream> {
+        let stream = self.inner.into_inner()?;
+        stream.set_nonblocking(false)?;
+        Ok(stream)
+    }
+
+    /// Receives data on the socket from the remote address to which it is connected,
+    /// without removing it from the input queue. On success, returns the number of
+    /// bytes peeked.
+    pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read_with(|stream| stream.peek(buf)).await
+    }
+
+    /// Receives data on the socket from the remote address to which it is connected. On
+    /// success, returns the number of bytes read.
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read_with(|stream| stream.recv(buf)).await
+    }
+
+    /// Sends data on the socket to the remote address to which it is connected. On
+    /// success, returns the number of bytes written.
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write_with(|stream| stream.send(buf)).await
+    }
+
+    /// Returns the socket address that this socket was created from.
+    pub fn local_addr(&self) -> io::Result<BtSocketAddr> {
+        self.inner.get_ref().local_addr()
+    }
+
+    /// Returns the socket address of the remote peer this socket was connected to.
+    pub fn peer_addr(&self) -> io::Result<BtSocketAddr> {
+        self.inner.get_ref().peer_addr()
+    }
+
+    /// Returns the socket protocol of this socket.
+    pub fn protocol(&self) -> BtProtocol {
+        self.inner.get_ref().protocol()
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.inner.get_ref().shutdown(how)
+    }
+
+    /// Splits this stream into a read half and a write half, which can be used
+    /// concurrently from the same task.
+    pub fn split(&self) -> (BtReadHalf<'_, Self>, BtWriteHalf<'_, Self>) {
+        split::split(self)
+    }
+
+    /// Splits this stream into owned read and write halves, which can be moved to
+    /// different tasks.
+    ///
+    /// The halves can be joined back into an `AsyncBtStream` with
+    /// [`BtOwnedReadHalf::reunite`].
+    ///
+    /// [`BtOwnedReadHalf::reunite`]: struct.BtOwnedReadHalf.html#method.reunite
+    pub fn into_split(self) -> (BtOwnedReadHalf<Self>, BtOwnedWriteHalf<Self>) {
+        split::into_split(self)
+    }
+}
+
+// Safety: the `Read` and `Write` implementations of `BtStream` never close or replace its
+// socket.
+unsafe impl IoSafe for BtStream {}
+
+impl AsyncRead for &AsyncBtStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.inner).poll_read(cx, buf)
+    }
+
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [io::IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.inner).poll_read_vectored(cx, bufs)
+    }
+}
+
+impl AsyncWrite for &AsyncBtStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.inner.get_ref().shutdown(Shutdown::Write))
+    }
+}
+
+impl AsyncRead for AsyncBtStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+
+    fn poll_read_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [io::IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_read_vectored(cx, bufs)
+    }
+}
+
+impl AsyncWrite for AsyncBtStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.inner.get_ref().shutdown(Shutdown::Write))
+    }
+}
@@ -0,0 +1,171 @@
+use std::io;
+use std::time::Duration;
+
+use super::{each_addr, BtAddr, BtAddrType, BtListener, BtProtocol, BtSocketAddr, BtStream};
+use crate::sys::bt::btc as c;
+use crate::sys::bt::Socket;
+use crate::sys_common::bt as bt_imp;
+
+/// Options used to open LE L2CAP connection-oriented channels, which use LE Credit Based
+/// Flow Control.
+///
+/// LE connection-oriented channels are opened by binding to or connecting to a
+/// [`BtSocketAddr`] with an LE address type and PSM, e.g. one created by
+/// [`BtSocketAddr::l2cap_le`]. The resulting sockets are regular [`BtListener`]s and
+/// [`BtStream`]s.
+///
+/// [`BtListener`]: struct.BtListener.html
+/// [`BtSocketAddr`]: struct.BtSocketAddr.html
+/// [`BtSocketAddr::l2cap_le`]: struct.BtSocketAddr.html#method.l2cap_le
+/// [`BtStream`]: struct.BtStream.html
+#[derive(Clone, Debug, Default)]
+pub struct BtLeCocOptions {
+    mtu: Option<u16>,
+    mps: Option<u16>,
+    credits: Option<u16>,
+    local_addr: Option<BtSocketAddr>,
+}
+
+impl BtLeCocOptions {
+    /// Creates a blank new set of options, using the OS defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the incoming MTU, i.e. the maximum size of an SDU that can be received.
+    ///
+    /// The MTU of an LE connection-oriented channel must be at least 23 bytes.
+    pub fn mtu(&mut self, mtu: u16) -> &mut Self {
+        self.mtu = Some(mtu);
+        self
+    }
+
+    /// Sets the incoming MPS, i.e. the maximum size of a PDU that can be received.
+    ///
+    /// The MPS of an LE connection-oriented channel must be between 23 and 65533 bytes.
+    /// On Linux, the kernel derives the MPS from the MTU and the LE link, so binding or
+    /// connecting with an MPS set fails with an error of kind `InvalidData`.
+    pub fn mps(&mut self, mps: u16) -> &mut Self {
+        self.mps = Some(mps);
+        self
+    }
+
+    /// Sets the number of credits initially given to the remote device, i.e. the number
+    /// of PDUs it may send before it has to wait for more credits.
+    ///
+    /// On Linux, the kernel derives the initial credits from the MTU, the MPS and the
+    /// socket's receive buffer, so binding or connecting with initial credits set fails
+    /// with an error of kind `InvalidData`.
+    pub fn credits(&mut self, credits: u16) -> &mut Self {
+        self.credits = Some(credits);
+        self
+    }
+
+    /// Sets the local address that outgoing channels are bound to before connecting.
+    ///
+    /// The address should be that of a local adapter, with an LE address type and a PSM
+    /// of 0. A random address type makes the adapter use its random address as the
+    /// source of the connection. By default, the OS picks the adapter and its public
+    /// address is used.
+    pub fn local_addr(&mut self, addr: BtSocketAddr) -> &mut Self {
+        self.local_addr = Some(addr);
+        self
+    }
+
+    /// Creates a new [`BtListener`] for LE connection-oriented channels, which will be
+    /// bound to the specified address.
+    ///
+    /// If `addrs` yields multiple addresses, `bind` will be attempted with each of the
+    /// addresses until one succeeds and returns the socket. If none of the addresses
+    /// succeed in creating a socket, the error returned from the last attempt (the last
+    /// address) is returned.
+    ///
+    /// [`BtListener`]: struct.BtListener.html
+    pub fn bind<'a, I>(&self, addrs: I) -> io::Result<BtListener>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        each_addr(addrs, |addr| {
+            check_le(addr)?;
+            bt_imp::BtListener::bind_with(addr, BtProtocol::L2CAP, c::SOCK_STREAM, |s| {
+                self.apply(s)
+            })
+        })
+        .map(BtListener)
+    }
+
+    /// Opens an LE connection-oriented channel to a remote device.
+    ///
+    /// If `addrs` yields multiple addresses, `connect` will be attempted with each of the
+    /// addresses until the underlying OS function returns no error. If the OS returns an
+    /// error for each of the specified addresses, the error returned from the last
+    /// connection attempt (the last address) is returned.
+    pub fn connect<'a, I>(&self, addrs: I) -> io::Result<BtStream>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        each_addr(addrs, |addr| {
+            check_le(addr)?;
+            bt_imp::BtStream::connect_with(addr, BtProtocol::L2CAP, c::SOCK_STREAM, |s| {
+                self.setup_connect(s)
+            })
+        })
+        .map(BtStream)
+    }
+
+    /// Opens an LE connection-oriented channel to a remote device with a timeout.
+    ///
+    /// It is an error to pass a zero `Duration` to this function.
+    pub fn connect_timeout(&self, addr: &BtSocketAddr, timeout: Duration) -> io::Result<BtStream> {
+        check_le(addr)?;
+        bt_imp::BtStream::connect_timeout_with(
+            addr,
+            BtProtocol::L2CAP,
+            c::SOCK_STREAM,
+            timeout,
+            |s| self.setup_connect(s),
+        )
+        .map(BtStream)
+    }
+
+    fn setup_connect(&self, socket: &Socket) -> io::Result<()> {
+        // The kernel only accepts LE socket options once the socket has an LE source
+        // address type, which requires binding it.
+        let local = match self.local_addr {
+            Some(addr) => {
+                check_le(&addr)?;
+                addr
+            }
+            None => {
+                let mut addr = BtSocketAddr::new(BtAddr([0; 6]));
+                addr.set_addr_type(BtAddrType::LePublic);
+                addr
+            }
+        };
+        bt_imp::bind(socket, &local, BtProtocol::L2CAP)?;
+        self.apply(socket)
+    }
+
+    fn apply(&self, socket: &Socket) -> io::Result<()> {
+        if let Some(mtu) = self.mtu {
+            socket.set_recv_mtu(mtu)?;
+        }
+        if let Some(mps) = self.mps {
+            socket.set_le_mps(mps)?;
+        }
+        if let Some(credits) = self.credits {
+            socket.set_le_credits(credits)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_le(addr: &BtSocketAddr) -> io::Result<()> {
+    match addr.addr_type() {
+        BtAddrType::LePublic | BtAddrType::LeRandom => Ok(()),
+        BtAddrType::BrEdr => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "LE connection-oriented channels require an LE address type",
+        )),
+    }
+}
@@ -0,0 +1,76 @@
+use std::io;
+
+use super::{class_of_device, BtStream};
+use crate::sys::bt as bt_sys;
+
+/// Information about the baseband link underlying a connected [`BtStream`].
+///
+/// The link can be monitored through the local HCI device that carries it, using
+/// [`rssi`], [`transmit_power_level`] and [`link_quality`].
+///
+/// [`BtStream`]: struct.BtStream.html
+/// [`rssi`]: #method.rssi
+/// [`transmit_power_level`]: #method.transmit_power_level
+/// [`link_quality`]: #method.link_quality
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct BtConnectionInfo {
+    dev_id: u16,
+    handle: u16,
+    class_of_device: u32,
+}
+
+impl BtConnectionInfo {
+    /// Returns the id of the local HCI device that carries the link.
+    pub fn dev_id(&self) -> u16 {
+        self.dev_id
+    }
+
+    /// Returns the HCI connection handle of the link.
+    pub fn handle(&self) -> u16 {
+        self.handle
+    }
+
+    /// Returns the class of device of the remote device, as a 24-bit value.
+    pub fn class_of_device(&self) -> u32 {
+        self.class_of_device
+    }
+
+    /// Reads the received signal strength of the link, in dB.
+    ///
+    /// For BR/EDR links this is the deviation from the golden receive power range, for LE
+    /// links the absolute signal strength in dBm.
+    pub fn rssi(&self) -> io::Result<i8> {
+        bt_sys::read_rssi(self.dev_id, self.handle)
+    }
+
+    /// Reads the current transmit power level of the link, in dBm.
+    pub fn transmit_power_level(&self) -> io::Result<i8> {
+        bt_sys::read_transmit_power_level(self.dev_id, self.handle, false)
+    }
+
+    /// Reads the maximum transmit power level of the link, in dBm.
+    pub fn max_transmit_power_level(&self) -> io::Result<i8> {
+        bt_sys::read_transmit_power_level(self.dev_id, self.handle, true)
+    }
+
+    /// Reads the quality of the link, from 0 (worst) to 255 (best).
+    ///
+    /// How the quality is measured is specific to the controller.
+    pub fn link_quality(&self) -> io::Result<u8> {
+        bt_sys::read_link_quality(self.dev_id, self.handle)
+    }
+}
+
+impl BtStream {
+    /// Returns the HCI connection handle and remote device class of this L2CAP or RFCOMM
+    /// connection, along with the local HCI device carrying it.
+    pub fn connection_info(&self) -> io::Result<BtConnectionInfo> {
+        let (handle, class) = self.0.socket().conninfo(self.0.protocol())?;
+        let dev_id = bt_sys::hci_dev_for_addr(self.0.local_addr()?.addr())?;
+        Ok(BtConnectionInfo {
+            dev_id,
+            handle,
+            class_of_device: class_of_device(class),
+        })
+    }
+}
@@ -0,0 +1,223 @@
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use super::{each_addr, BtProtocol, BtSocketAddr};
+use crate::sys_common::bt as bt_imp;
+use crate::sys_common::{AsInner, FromInner, IntoInner};
+
+/// A connectionless L2CAP socket.
+///
+/// After creating a `BtDatagram` by [`bind`]ing it to a Bluetooth address, data can be
+/// [sent to] and [received from] any other socket address.
+///
+/// Although L2CAP is a connectionless protocol in this mode, this implementation
+/// provides an interface to set an address where data should be sent and received from.
+/// After setting a remote address with [`connect`], data can be sent to and received
+/// from that address with [`send`] and [`recv`].
+///
+/// The socket will be closed when the value is dropped.
+///
+/// [`bind`]: #method.bind
+/// [`connect`]: #method.connect
+/// [received from]: #method.recv_from
+/// [`recv`]: #method.recv
+/// [`send`]: #method.send
+/// [sent to]: #method.send_to
+pub struct BtDatagram(bt_imp::BtDatagram);
+
+impl BtDatagram {
+    /// Creates a connectionless L2CAP socket bound to the specified address.
+    ///
+    /// Binding with a PSM of 0 will request that the OS assigns one to this socket. The
+    /// PSM allocated can be queried via the [`local_addr`] method.
+    ///
+    /// If `addrs` yields multiple addresses, `bind` will be attempted with each of the
+    /// addresses until one succeeds and returns the socket. If none of the addresses
+    /// succeed in creating a socket, the error returned from the last attempt (the last
+    /// address) is returned.
+    ///
+    /// [`local_addr`]: #method.local_addr
+    pub fn bind<'a, I>(addrs: I) -> io::Result<Self>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        each_addr(addrs, |addr| {
+            bt_imp::BtDatagram::bind(addr, BtProtocol::L2CAP)
+        })
+        .map(BtDatagram)
+    }
+
+    /// Connects this socket to a remote address, allowing the [`send`] and [`recv`]
+    /// methods to be used to send data and also applies filters to only receive data
+    /// from the specified address.
+    ///
+    /// If `addrs` yields multiple addresses, `connect` will be attempted with each of the
+    /// addresses until the underlying OS function returns no error. If the OS returns an
+    /// error for each of the specified addresses, the error returned from the last
+    /// connection attempt (the last address) is returned.
+    ///
+    /// [`recv`]: #method.recv
+    /// [`send`]: #method.send
+    pub fn connect<'a, I>(&self, addrs: I) -> io::Result<()>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        each_addr(addrs, |addr| self.0.connect(addr))
+    }
+
+    /// Receives a single datagram message on the socket. On success, returns the number
+    /// of bytes read and the origin.
+    ///
+    /// The function must be called with valid byte array `buf` of sufficient size to hold
+    /// the message bytes. If a message is too long to fit in the supplied buffer, excess
+    /// bytes may be discarded.
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, BtSocketAddr)> {
+        self.0.recv_from(buf)
+    }
+
+    /// Receives a single datagram message on the socket, without removing it from the
+    /// queue. On success, returns the number of bytes read and the origin.
+    ///
+    /// The function must be called with valid byte array `buf` of sufficient size to hold
+    /// the message bytes. If a message is too long to fit in the supplied buffer, excess
+    /// bytes may be discarded.
+    ///
+    /// Successive calls return the same data. This is accomplished by passing `MSG_PEEK`
+    /// as a flag to the underlying `recvfrom` system call.
+    pub fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, BtSocketAddr)> {
+        self.0.peek_from(buf)
+    }
+
+    /// Sends data on the socket to the given address. On success, returns the number of
+    /// bytes written.
+    pub fn send_to(&self, buf: &[u8], dst: &BtSocketAddr) -> io::Result<usize> {
+        self.0.send_to(buf, dst)
+    }
+
+    /// Receives a single datagram message on the socket from the remote address to which
+    /// it is connected. On success, returns the number of bytes read.
+    ///
+    /// The [`connect`] method will connect this socket to a remote address. This method
+    /// will fail if the socket is not connected.
+    ///
+    /// [`connect`]: #method.connect
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.recv(buf)
+    }
+
+    /// Receives a single datagram message on the socket from the remote address to which
+    /// it is connected, without removing the message from input queue. On success,
+    /// returns the number of bytes peeked.
+    ///
+    /// The [`connect`] method will connect this socket to a remote address. This method
+    /// will fail if the socket is not connected.
+    ///
+    /// [`connect`]: #method.connect
+    pub fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.peek(buf)
+    }
+
+    /// Sends data on the socket to the remote address to which it is connected.
+    ///
+    /// The [`connect`] method will connect this socket to a remote address. This method
+    /// will fail if the socket is not connected.
+    ///
+    /// [`connect`]: #method.connect
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.0.send(buf)
+    }
+
+    /// Returns the socket address that this socket was created from.
+    pub fn local_addr(&self) -> io::Result<BtSocketAddr> {
+        self.0.local_addr()
+    }
+
+    /// Returns the socket address of the remote peer this socket was connected to.
+    pub fn peer_addr(&self) -> io::Result<BtSocketAddr> {
+        self.0.peer_addr()
+    }
+
+    /// Gets the value of the `SO_ERROR` option on this socket.
+    ///
+    /// This will retrieve the stored error in the underlying socket, clearing
+    /// the field in the process. This can be useful for checking errors between
+    /// calls.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.0.take_error()
+    }
+
+    /// Returns the read timeout of this socket.
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    /// Sets the read timeout to the timeout specified.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    /// Sets the write timeout to the timeout specified.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
+    /// Moves this socket into or out of nonblocking mode.
+    ///
+    /// This will result in `recv`, `recv_from`, `send`, and `send_to` operations becoming
+    /// nonblocking, i.e., immediately returning from their calls. If the IO operation is
+    /// successful, `Ok` is returned and no further action is required. If the IO
+    /// operation could not be completed and needs to be retried, an error with kind
+    /// [`io::ErrorKind::WouldBlock`] is returned.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    ///
+    /// The returned `BtDatagram` is a reference to the same socket that this object
+    /// references. Both handles will read and write the same PSM, and options set on
+    /// one socket will be propagated to the other.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        self.0.duplicate().map(BtDatagram)
+    }
+}
+
+impl fmt::Debug for BtDatagram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl AsInner<bt_imp::BtDatagram> for BtDatagram {
+    fn as_inner(&self) -> &bt_imp::BtDatagram {
+        &self.0
+    }
+}
+
+impl FromInner<bt_imp::BtDatagram> for BtDatagram {
+    fn from_inner(inner: bt_imp::BtDatagram) -> BtDatagram {
+        BtDatagram(inner)
+    }
+}
+
+impl IntoInner<bt_imp::BtDatagram> for BtDatagram {
+    fn into_inner(self) -> bt_imp::BtDatagram {
+        self.0
+    }
+}
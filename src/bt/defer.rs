@@ -0,0 +1,98 @@
+use std::fmt;
+use std::io;
+
+use super::{class_of_device, security, BtListener, BtSocketAddr, BtStream, SecurityInfo};
+use crate::sys_common::bt as bt_imp;
+
+/// An incoming connection whose setup has been deferred until the application authorizes
+/// or rejects it.
+///
+/// Pending connections are returned by [`BtListener::accept_pending`]. The remote device
+/// is kept waiting until either [`authorize`] or [`reject`] is called; dropping the value
+/// rejects the connection.
+///
+/// [`BtListener::accept_pending`]: struct.BtListener.html#method.accept_pending
+/// [`authorize`]: #method.authorize
+/// [`reject`]: #method.reject
+pub struct BtPendingConnection {
+    inner: bt_imp::BtStream,
+    peer: BtSocketAddr,
+}
+
+impl BtPendingConnection {
+    /// Returns the socket address of the remote device.
+    pub fn peer_addr(&self) -> BtSocketAddr {
+        self.peer
+    }
+
+    /// Returns the class of device of the remote device, as a 24-bit value.
+    pub fn class_of_device(&self) -> io::Result<u32> {
+        let (_, class) = self.inner.socket().conninfo(self.inner.protocol())?;
+        Ok(class_of_device(class))
+    }
+
+    /// Returns the current security level and encryption key size of the link to the
+    /// remote device.
+    pub fn security(&self) -> io::Result<SecurityInfo> {
+        security(self.inner.socket())
+    }
+
+    /// Accepts the connection, blocking until it is fully established.
+    pub fn authorize(self) -> io::Result<BtStream> {
+        self.inner.socket().authorize_deferred()?;
+        Ok(BtStream(self.inner))
+    }
+
+    /// Rejects the connection.
+    pub fn reject(self) {}
+}
+
+impl fmt::Debug for BtPendingConnection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BtPendingConnection")
+            .field("peer", &self.peer)
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl BtListener {
+    /// Enables or disables deferred setup (`BT_DEFER_SETUP`) for connections accepted by
+    /// this listener.
+    ///
+    /// Deferred setup must be enabled to use [`accept_pending`].
+    ///
+    /// [`accept_pending`]: #method.accept_pending
+    pub fn set_defer_setup(&self, enabled: bool) -> io::Result<()> {
+        self.0.socket().set_defer_setup(enabled)
+    }
+
+    /// Returns whether deferred setup is enabled for this listener.
+    pub fn defer_setup(&self) -> io::Result<bool> {
+        self.0.socket().defer_setup()
+    }
+
+    /// Accepts a new incoming connection from this listener, without completing its
+    /// setup.
+    ///
+    /// This function will block the calling thread until a remote device requests a
+    /// connection. The connection is then held pending until it is authorized or rejected
+    /// through the returned [`BtPendingConnection`].
+    ///
+    /// An error of kind `InvalidInput` is returned if deferred setup has not been enabled
+    /// with [`set_defer_setup`].
+    ///
+    /// [`BtPendingConnection`]: struct.BtPendingConnection.html
+    /// [`set_defer_setup`]: #method.set_defer_setup
+    pub fn accept_pending(&self) -> io::Result<BtPendingConnection> {
+        if !self.defer_setup()? {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "deferred setup is not enabled on this listener",
+            ));
+        }
+        self.0
+            .accept()
+            .map(|(inner, peer)| BtPendingConnection { inner, peer })
+    }
+}
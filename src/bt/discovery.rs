@@ -0,0 +1,609 @@
+use std::io;
+use std::time::Duration;
+#[cfg(unix)]
+use std::time::Instant;
+
+use super::{class_of_device, BtAddr};
+#[cfg(unix)]
+use super::{HciFilter, HciPacket, HciPacketType, HciSocket};
+use crate::sys::bt as bt_sys;
+
+/// The inquiry access code that an inquiry is made with, which determines which
+/// discoverable devices respond.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum InquiryAccessCode {
+    /// The general inquiry access code (GIAC), to which all discoverable devices respond.
+    General,
+    /// The limited inquiry access code (LIAC), to which only devices that are discoverable
+    /// for a limited time respond.
+    Limited,
+}
+
+/// The major device class of a device, as given by its class of device.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum MajorDeviceClass {
+    /// A device that does not fit any of the other classes.
+    Miscellaneous,
+    /// A desktop, laptop, server or handheld computer.
+    Computer,
+    /// A cellular, cordless or smart phone, or a modem.
+    Phone,
+    /// A LAN or network access point.
+    NetworkAccessPoint,
+    /// An audio or video device, such as a headset, loudspeaker or television.
+    AudioVideo,
+    /// A peripheral, such as a keyboard, mouse or joystick.
+    Peripheral,
+    /// An imaging device, such as a printer, scanner or camera.
+    Imaging,
+    /// A wearable device, such as a watch or glasses.
+    Wearable,
+    /// A toy, such as a robot, vehicle or game.
+    Toy,
+    /// A health device, such as a thermometer or heart rate monitor.
+    Health,
+    /// A device whose class has not been specified.
+    Uncategorized,
+    /// A major device class that is reserved by the specification.
+    Reserved(u8),
+}
+
+/// A device found by [`discover_devices`] or [`discover_devices_with`].
+///
+/// [`discover_devices`]: fn.discover_devices.html
+/// [`discover_devices_with`]: fn.discover_devices_with.html
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct DiscoveredDevice {
+    addr: BtAddr,
+    class_of_device: u32,
+    page_scan_repetition_mode: Option<u8>,
+    clock_offset: Option<u16>,
+    rssi: Option<i8>,
+}
+
+/// Options which can be used to configure how nearby devices are discovered.
+///
+/// [`discover_devices`] is equivalent to discovering with the default options, which
+/// make a 5.12 second inquiry with the general inquiry access code on the default
+/// adapter, without limiting the number of responses, and without using previously
+/// cached results.
+///
+/// [`discover_devices`]: fn.discover_devices.html
+#[derive(Clone, Debug)]
+pub struct DiscoveryOptions {
+    length: u8,
+    max_responses: u8,
+    flush_cache: bool,
+    access_code: InquiryAccessCode,
+    adapter: Option<BtAddr>,
+    class_filter: Option<(u32, u32)>,
+    rssi: bool,
+}
+
+/// The unit of inquiry lengths, 1.28 seconds.
+const INQUIRY_LENGTH_UNIT_MS: u128 = 1280;
+
+/// The longest possible inquiry, in units of 1.28 seconds.
+const MAX_INQUIRY_LENGTH: u8 = 0x30;
+
+impl DiscoveryOptions {
+    /// Creates a new set of options with the default settings.
+    pub fn new() -> Self {
+        Self {
+            length: 4,
+            max_responses: 0,
+            flush_cache: true,
+            access_code: InquiryAccessCode::General,
+            adapter: None,
+            class_filter: None,
+            rssi: false,
+        }
+    }
+
+    /// Sets how long the inquiry lasts.
+    ///
+    /// Inquiries last a multiple of 1.28 seconds, so `length` is rounded up to one,
+    /// between 1.28 and 61.44 seconds.
+    pub fn inquiry_length(&mut self, length: Duration) -> &mut Self {
+        let units = length.as_millis().div_ceil(INQUIRY_LENGTH_UNIT_MS);
+        self.length = units.clamp(1, u128::from(MAX_INQUIRY_LENGTH)) as u8;
+        self
+    }
+
+    /// Sets the number of responses after which the inquiry ends early, or 0 for no limit.
+    ///
+    /// Devices that are filtered out by [`class_filter`] count towards the limit.
+    ///
+    /// [`class_filter`]: #method.class_filter
+    pub fn max_responses(&mut self, max: u8) -> &mut Self {
+        self.max_responses = max;
+        self
+    }
+
+    /// Sets whether the results of previous inquiries are discarded, rather than
+    /// reported alongside the devices found by this one.
+    ///
+    /// On Windows, no inquiry is made unless the cache is flushed, so only previously
+    /// found devices are returned.
+    pub fn flush_cache(&mut self, flush: bool) -> &mut Self {
+        self.flush_cache = flush;
+        self
+    }
+
+    /// Sets the inquiry access code that the inquiry is made with.
+    pub fn access_code(&mut self, code: InquiryAccessCode) -> &mut Self {
+        self.access_code = code;
+        self
+    }
+
+    /// Sets the address of the local adapter that makes the inquiry.
+    ///
+    /// Selecting the adapter is not supported on Windows, where all local radios are
+    /// used.
+    pub fn adapter(&mut self, addr: BtAddr) -> &mut Self {
+        self.adapter = Some(addr);
+        self
+    }
+
+    /// Only reports devices whose class of device, masked by `mask`, equals `class`.
+    ///
+    /// For example, a `mask` of `0x1f00` and a `class` of `0x0400` selects devices with
+    /// the audio/video major device class.
+    pub fn class_filter(&mut self, mask: u32, class: u32) -> &mut Self {
+        self.class_filter = Some((mask, class & mask));
+        self
+    }
+
+    /// Sets whether the signal strength of responses is reported.
+    ///
+    /// Controllers only report the signal strength when their inquiry mode is set to
+    /// inquiry with RSSI or extended inquiry, for example with `hciconfig hci0 inqmode 1`.
+    /// This makes the inquiry over a raw HCI socket, which requires the `CAP_NET_RAW`
+    /// capability, and bypasses the cache of previously found devices.
+    ///
+    /// Reporting the signal strength is not supported on Windows.
+    pub fn rssi(&mut self, rssi: bool) -> &mut Self {
+        self.rssi = rssi;
+        self
+    }
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiscoveredDevice {
+    /// Returns the address of the device.
+    pub fn addr(&self) -> &BtAddr {
+        &self.addr
+    }
+
+    /// Returns the raw class of device of the device.
+    pub fn class_of_device(&self) -> u32 {
+        self.class_of_device
+    }
+
+    /// Returns the major service classes of the device, a bit mask of the services it
+    /// provides, such as audio (bit 8) or telephony (bit 9).
+    pub fn service_classes(&self) -> u16 {
+        (self.class_of_device >> 13) as u16 & 0x07ff
+    }
+
+    /// Returns the major device class of the device.
+    pub fn major_device_class(&self) -> MajorDeviceClass {
+        match (self.class_of_device >> 8) as u8 & 0x1f {
+            0x00 => MajorDeviceClass::Miscellaneous,
+            0x01 => MajorDeviceClass::Computer,
+            0x02 => MajorDeviceClass::Phone,
+            0x03 => MajorDeviceClass::NetworkAccessPoint,
+            0x04 => MajorDeviceClass::AudioVideo,
+            0x05 => MajorDeviceClass::Peripheral,
+            0x06 => MajorDeviceClass::Imaging,
+            0x07 => MajorDeviceClass::Wearable,
+            0x08 => MajorDeviceClass::Toy,
+            0x09 => MajorDeviceClass::Health,
+            0x1f => MajorDeviceClass::Uncategorized,
+            class => MajorDeviceClass::Reserved(class),
+        }
+    }
+
+    /// Returns the minor device class of the device, whose meaning depends on the major
+    /// device class.
+    pub fn minor_device_class(&self) -> u8 {
+        (self.class_of_device >> 2) as u8 & 0x3f
+    }
+
+    /// Returns the page scan repetition mode of the device, which is not reported on
+    /// Windows.
+    pub fn page_scan_repetition_mode(&self) -> Option<u8> {
+        self.page_scan_repetition_mode
+    }
+
+    /// Returns the offset between the clocks of the device and the local adapter, as
+    /// reported by the controller, which is not reported on Windows.
+    ///
+    /// Bits 14-0 hold bits 16-2 of the offset.
+    pub fn clock_offset(&self) -> Option<u16> {
+        self.clock_offset
+    }
+
+    /// Returns the signal strength of the device's response in dBm, if it was reported.
+    ///
+    /// See [`DiscoveryOptions::rssi`] for when it is.
+    ///
+    /// [`DiscoveryOptions::rssi`]: struct.DiscoveryOptions.html#method.rssi
+    pub fn rssi(&self) -> Option<i8> {
+        self.rssi
+    }
+}
+
+/// Discovers nearby Bluetooth devices using the given options.
+///
+/// This blocks for the length of the inquiry.
+pub fn discover_devices_with(options: &DiscoveryOptions) -> io::Result<Vec<DiscoveredDevice>> {
+    let lap = match options.access_code {
+        InquiryAccessCode::General => [0x33, 0x8b, 0x9e],
+        InquiryAccessCode::Limited => [0x00, 0x8b, 0x9e],
+    };
+    let devices = if options.rssi {
+        inquiry_with_rssi(options, lap)?
+    } else {
+        bt_sys::inquiry(
+            options.adapter.as_ref(),
+            options.length,
+            options.max_responses,
+            options.flush_cache,
+            lap,
+        )?
+        .into_iter()
+        .map(
+            |(addr, class, page_scan_repetition_mode, clock_offset)| DiscoveredDevice {
+                addr,
+                class_of_device: class_of_device(class),
+                page_scan_repetition_mode,
+                clock_offset,
+                rssi: None,
+            },
+        )
+        .collect()
+    };
+
+    Ok(devices
+        .into_iter()
+        .filter(|device| match options.class_filter {
+            Some((mask, value)) => device.class_of_device & mask == value,
+            None => true,
+        })
+        .collect())
+}
+
+/// Discovers nearby Bluetooth devices.
+///
+/// This makes an inquiry with the default [`DiscoveryOptions`], which blocks for about
+/// five seconds. Use [`discover_devices_with`] to configure the inquiry, for example to
+/// report the RSSI of the devices.
+///
+/// [`DiscoveryOptions`]: struct.DiscoveryOptions.html
+/// [`discover_devices_with`]: fn.discover_devices_with.html
+pub fn discover_devices() -> io::Result<Vec<DiscoveredDevice>> {
+    discover_devices_with(&DiscoveryOptions::new())
+}
+
+#[cfg(windows)]
+fn inquiry_with_rssi(_: &DiscoveryOptions, _: [u8; 3]) -> io::Result<Vec<DiscoveredDevice>> {
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "Reporting the RSSI of discovered devices is currently not supported on Windows",
+    ))
+}
+
+#[cfg(unix)]
+const OGF_LINK_CTL: u8 = 0x01;
+#[cfg(unix)]
+const OCF_INQUIRY: u16 = 0x0001;
+#[cfg(unix)]
+const OCF_INQUIRY_CANCEL: u16 = 0x0002;
+
+#[cfg(unix)]
+const EVT_INQUIRY_COMPLETE: u8 = 0x01;
+#[cfg(unix)]
+const EVT_INQUIRY_RESULT: u8 = 0x02;
+#[cfg(unix)]
+const EVT_CMD_STATUS: u8 = 0x0f;
+#[cfg(unix)]
+const EVT_INQUIRY_RESULT_WITH_RSSI: u8 = 0x22;
+#[cfg(unix)]
+const EVT_EXTENDED_INQUIRY_RESULT: u8 = 0x2f;
+
+/// Makes an inquiry by sending the HCI command and collecting the results from the
+/// events, as the kernel's inquiry cache does not keep the RSSI of responses.
+#[cfg(unix)]
+fn inquiry_with_rssi(
+    options: &DiscoveryOptions,
+    lap: [u8; 3],
+) -> io::Result<Vec<DiscoveredDevice>> {
+    let dev_id = match &options.adapter {
+        Some(addr) => bt_sys::hci_dev_for_addr(addr)?,
+        None => bt_sys::default_hci_dev()?,
+    };
+    let socket = HciSocket::open(dev_id)?;
+
+    let mut filter = HciFilter::new();
+    filter.set_packet_type(HciPacketType::Event);
+    for &event in &[
+        EVT_INQUIRY_COMPLETE,
+        EVT_INQUIRY_RESULT,
+        EVT_CMD_STATUS,
+        EVT_INQUIRY_RESULT_WITH_RSSI,
+        EVT_EXTENDED_INQUIRY_RESULT,
+    ] {
+        filter.set_event(event);
+    }
+    socket.set_filter(&filter)?;
+
+    let params = vec![
+        lap[0],
+        lap[1],
+        lap[2],
+        options.length,
+        options.max_responses,
+    ];
+    socket.send(&HciPacket::command(OGF_LINK_CTL, OCF_INQUIRY, params))?;
+
+    // Leave the controller some slack beyond the inquiry length to report completion.
+    let length = Duration::from_millis(INQUIRY_LENGTH_UNIT_MS as u64 * u64::from(options.length));
+    let deadline = Instant::now() + length + Duration::from_secs(5);
+    let opcode = (u16::from(OGF_LINK_CTL) << 10) | OCF_INQUIRY;
+
+    let mut devices: Vec<DiscoveredDevice> = Vec::new();
+    loop {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        if timeout == Duration::from_secs(0) {
+            let _ = socket.send(&HciPacket::command(
+                OGF_LINK_CTL,
+                OCF_INQUIRY_CANCEL,
+                vec![],
+            ));
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "the controller did not complete the inquiry",
+            ));
+        }
+        socket.set_read_timeout(Some(timeout))?;
+
+        let (code, params) = match socket.recv() {
+            Ok(HciPacket::Event { code, params }) => (code, params),
+            Ok(_) => continue,
+            Err(ref e)
+                if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut =>
+            {
+                continue
+            }
+            Err(e) => return Err(e),
+        };
+        match code {
+            EVT_CMD_STATUS if params.len() >= 4 => {
+                if u16::from_le_bytes([params[2], params[3]]) == opcode && params[0] != 0 {
+                    return Err(inquiry_failed(params[0]));
+                }
+            }
+            EVT_INQUIRY_COMPLETE => match params.first() {
+                Some(0) | None => return Ok(devices),
+                Some(&status) => return Err(inquiry_failed(status)),
+            },
+            _ => {
+                for device in inquiry_results(code, &params) {
+                    match devices.iter_mut().find(|known| known.addr == device.addr) {
+                        Some(known) => *known = device,
+                        None => devices.push(device),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn inquiry_failed(status: u8) -> io::Error {
+    io::Error::other(format!("inquiry failed with HCI status {:#04x}", status))
+}
+
+/// Parses the devices reported by an inquiry result event.
+#[cfg(unix)]
+fn inquiry_results(code: u8, params: &[u8]) -> Vec<DiscoveredDevice> {
+    let (&count, entries) = match params.split_first() {
+        Some(split) if *split.0 > 0 => split,
+        _ => return Vec::new(),
+    };
+    let count = usize::from(count);
+
+    // The offsets of the class of device, clock offset and RSSI in each entry, after the
+    // address and page scan repetition mode.
+    let (len, class, rssi) = match code {
+        EVT_INQUIRY_RESULT => (14, 9, None),
+        // Some controllers include the reserved page scan mode, like in inquiry results.
+        EVT_INQUIRY_RESULT_WITH_RSSI if entries.len() / count == 15 => (15, 9, Some(14)),
+        EVT_INQUIRY_RESULT_WITH_RSSI => (14, 8, Some(13)),
+        EVT_EXTENDED_INQUIRY_RESULT => (entries.len() / count, 8, Some(13)),
+        _ => return Vec::new(),
+    };
+    if len < 14 {
+        return Vec::new();
+    }
+
+    entries
+        .chunks_exact(len)
+        .take(count)
+        .map(|entry| {
+            let mut addr = [0; 6];
+            addr.copy_from_slice(&entry[..6]);
+            DiscoveredDevice {
+                addr: BtAddr(addr),
+                class_of_device: class_of_device([
+                    entry[class],
+                    entry[class + 1],
+                    entry[class + 2],
+                ]),
+                page_scan_repetition_mode: Some(entry[6]),
+                clock_offset: Some(u16::from_le_bytes([entry[class + 3], entry[class + 4]])),
+                rssi: rssi.map(|i| entry[i] as i8),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inquiry_length() {
+        let cases = [
+            (Duration::from_secs(0), 0x01),
+            (Duration::from_millis(1), 0x01),
+            (Duration::from_millis(1280), 0x01),
+            (Duration::from_millis(1281), 0x02),
+            (Duration::from_millis(5120), 0x04),
+            (Duration::from_secs(10), 0x08),
+            (Duration::from_millis(61_440), 0x30),
+            (Duration::from_millis(61_441), 0x30),
+            (Duration::from_secs(3600), 0x30),
+        ];
+        for &(length, units) in &cases {
+            let mut options = DiscoveryOptions::new();
+            options.inquiry_length(length);
+            assert_eq!(options.length, units, "{:?}", length);
+        }
+        assert_eq!(DiscoveryOptions::new().length, 0x04);
+    }
+
+    fn device(
+        addr: [u8; 6],
+        class_of_device: u32,
+        page_scan_repetition_mode: u8,
+        clock_offset: u16,
+        rssi: Option<i8>,
+    ) -> DiscoveredDevice {
+        DiscoveredDevice {
+            addr: BtAddr(addr),
+            class_of_device,
+            page_scan_repetition_mode: Some(page_scan_repetition_mode),
+            clock_offset: Some(clock_offset),
+            rssi,
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn inquiry_result_events() {
+        const PHONE: [u8; 6] = [0x66, 0x55, 0x44, 0x33, 0x22, 0x11];
+        const HEADSET: [u8; 6] = [0x0b, 0x8a, 0x4f, 0x2e, 0x1d, 0x00];
+
+        let mut extended = vec![
+            0x01, // Num_Responses
+            0x66, 0x55, 0x44, 0x33, 0x22, 0x11, // BD_ADDR
+            0x01, // Page_Scan_Repetition_Mode
+            0x00, // Reserved
+            0x0c, 0x02, 0x5a, // Class_Of_Device
+            0xe1, 0x3a, // Clock_Offset
+            0xc4, // RSSI
+            0x06, 0x09, b'P', b'h', b'o', b'n', b'e', // Extended_Inquiry_Response
+        ];
+        extended.resize(1 + 14 + 240, 0);
+
+        let cases: Vec<(u8, Vec<u8>, Vec<DiscoveredDevice>)> = vec![
+            (
+                EVT_INQUIRY_RESULT,
+                vec![
+                    0x02, // Num_Responses
+                    0x66, 0x55, 0x44, 0x33, 0x22, 0x11, // BD_ADDR
+                    0x01, // Page_Scan_Repetition_Mode
+                    0x00, 0x00, // Reserved
+                    0x0c, 0x02, 0x5a, // Class_Of_Device
+                    0xe1, 0x3a, // Clock_Offset
+                    0x0b, 0x8a, 0x4f, 0x2e, 0x1d, 0x00, // BD_ADDR
+                    0x02, // Page_Scan_Repetition_Mode
+                    0x00, 0x00, // Reserved
+                    0x04, 0x04, 0x24, // Class_Of_Device
+                    0x34, 0x12, // Clock_Offset
+                ],
+                vec![
+                    device(PHONE, 0x5a020c, 0x01, 0x3ae1, None),
+                    device(HEADSET, 0x240404, 0x02, 0x1234, None),
+                ],
+            ),
+            (
+                EVT_INQUIRY_RESULT_WITH_RSSI,
+                vec![
+                    0x01, // Num_Responses
+                    0x66, 0x55, 0x44, 0x33, 0x22, 0x11, // BD_ADDR
+                    0x01, // Page_Scan_Repetition_Mode
+                    0x00, // Reserved
+                    0x0c, 0x02, 0x5a, // Class_Of_Device
+                    0xe1, 0x3a, // Clock_Offset
+                    0xc4, // RSSI
+                ],
+                vec![device(PHONE, 0x5a020c, 0x01, 0x3ae1, Some(-60))],
+            ),
+            (
+                EVT_INQUIRY_RESULT_WITH_RSSI,
+                vec![
+                    0x01, // Num_Responses
+                    0x0b, 0x8a, 0x4f, 0x2e, 0x1d, 0x00, // BD_ADDR
+                    0x02, // Page_Scan_Repetition_Mode
+                    0x00, 0x00, // Reserved
+                    0x04, 0x04, 0x24, // Class_Of_Device
+                    0x34, 0x12, // Clock_Offset
+                    0xb0, // RSSI
+                ],
+                vec![device(HEADSET, 0x240404, 0x02, 0x1234, Some(-80))],
+            ),
+            (
+                EVT_EXTENDED_INQUIRY_RESULT,
+                extended,
+                vec![device(PHONE, 0x5a020c, 0x01, 0x3ae1, Some(-60))],
+            ),
+            (EVT_INQUIRY_RESULT, vec![0x00], vec![]),
+            (EVT_INQUIRY_RESULT, vec![], vec![]),
+            (
+                EVT_INQUIRY_RESULT_WITH_RSSI,
+                vec![0x01, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11, 0x01],
+                vec![],
+            ),
+            (EVT_CMD_STATUS, vec![0x01, 0x01, 0x01, 0x04], vec![]),
+        ];
+        for (code, params, expected) in &cases {
+            assert_eq!(
+                inquiry_results(*code, params),
+                *expected,
+                "event {:#04x} {:02x?}",
+                code,
+                params
+            );
+        }
+    }
+
+    #[test]
+    fn class_of_device_fields() {
+        let cases = [
+            (0x5a020c, MajorDeviceClass::Phone, 0x03, 0x02d0),
+            (0x240404, MajorDeviceClass::AudioVideo, 0x01, 0x0120),
+            (0x00010c, MajorDeviceClass::Computer, 0x03, 0x0000),
+            (0x000000, MajorDeviceClass::Miscellaneous, 0x00, 0x0000),
+            (0x000900, MajorDeviceClass::Health, 0x00, 0x0000),
+            (0x001f00, MajorDeviceClass::Uncategorized, 0x00, 0x0000),
+            (0x000b00, MajorDeviceClass::Reserved(0x0b), 0x00, 0x0000),
+            (0xffe000, MajorDeviceClass::Miscellaneous, 0x00, 0x07ff),
+        ];
+        for &(class, major, minor, services) in &cases {
+            let device = device([0; 6], class, 0, 0, None);
+            assert_eq!(device.major_device_class(), major, "{:#08x}", class);
+            assert_eq!(device.minor_device_class(), minor, "{:#08x}", class);
+            assert_eq!(device.service_classes(), services, "{:#08x}", class);
+        }
+
+        assert_eq!(class_of_device([0x0c, 0x02, 0x5a]), 0x5a020c);
+    }
+}
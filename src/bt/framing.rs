@@ -0,0 +1,1014 @@
+//! Message framing over Bluetooth streams.
+//!
+//! RFCOMM and L2CAP streams carry bytes, not messages. The framings in this module split
+//! the bytes into frames, and are used with [`Framed`] to send and receive frames over
+//! any `Read + Write` type, such as a [`BtStream`] or one of its halves.
+//!
+//! With the `tokio-util` feature enabled, each framing also implements the
+//! [`Decoder`] and [`Encoder`] traits of `tokio_util::codec`.
+//!
+//! [`Framed`]: struct.Framed.html
+//! [`BtStream`]: ../struct.BtStream.html
+//! [`Decoder`]: https://docs.rs/tokio-util/0.7/tokio_util/codec/trait.Decoder.html
+//! [`Encoder`]: https://docs.rs/tokio-util/0.7/tokio_util/codec/trait.Encoder.html
+
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+/// The default maximum length of a frame, 8 MiB.
+const DEFAULT_MAX_FRAME_LEN: usize = 8 * 1024 * 1024;
+
+/// The number of bytes read from the underlying stream at once.
+const READ_CHUNK_LEN: usize = 4096;
+
+/// A way of splitting a byte stream into frames.
+pub trait Framing {
+    /// Attempts to decode a frame from the start of `src`.
+    ///
+    /// Returns the frame and the number of bytes of `src` that it took up, or `None` if
+    /// `src` does not contain a whole frame yet. Until a frame is returned, each call
+    /// passes the bytes of the previous one followed by any newly received bytes, so
+    /// framings may remember how far they have searched `src`.
+    fn decode(&mut self, src: &[u8]) -> io::Result<Option<(Vec<u8>, usize)>>;
+
+    /// Attempts to decode a frame from the start of `src` once the stream has ended.
+    ///
+    /// By default, this is an error of kind `UnexpectedEof` if `src` holds a partial
+    /// frame.
+    fn decode_eof(&mut self, src: &[u8]) -> io::Result<Option<(Vec<u8>, usize)>> {
+        match self.decode(src)? {
+            None if !src.is_empty() => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "stream ended within a frame",
+            )),
+            frame => Ok(frame),
+        }
+    }
+
+    /// Returns the number of bytes at the start of `src` to discard after decoding them
+    /// failed, so that decoding can resume with the next frame.
+    ///
+    /// By default, all of `src` is discarded.
+    fn recover(&mut self, src: &[u8]) -> usize {
+        src.len()
+    }
+
+    /// Encodes `frame`, appending it to `dst`.
+    fn encode(&mut self, frame: &[u8], dst: &mut Vec<u8>) -> io::Result<()>;
+}
+
+/// The byte order of a length header.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Endianness {
+    /// The most significant byte comes first.
+    Big,
+    /// The least significant byte comes first.
+    Little,
+}
+
+/// Frames that are prefixed by their length.
+///
+/// By default, the length is a 4-byte, big-endian header counting the bytes of the frame
+/// that follow it. The start of the next frame cannot be found after an invalid header,
+/// so all received bytes are discarded when decoding fails.
+#[derive(Clone, Copy, Debug)]
+pub struct LengthDelimited {
+    header_len: usize,
+    endianness: Endianness,
+    max_frame_len: usize,
+}
+
+/// Line ending characters.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum LineEnding {
+    /// A line feed, `\n`.
+    Lf,
+    /// A carriage return, `\r`, as used by AT commands.
+    Cr,
+    /// A carriage return followed by a line feed, `\r\n`, as used by AT responses.
+    CrLf,
+}
+
+/// Frames that are terminated by a line ending, as used by AT-style protocols.
+///
+/// Lines are decoded up to any of `\n`, `\r` or `\r\n`, which are not part of the frame.
+/// A line is decoded as soon as its `\r` arrives, and a `\n` right after it is skipped.
+/// Frames are encoded followed by a configurable line ending, `\r\n` by default.
+///
+/// A line that is too long is discarded up to and including its line ending.
+#[derive(Clone, Copy, Debug)]
+pub struct LineDelimited {
+    ending: LineEnding,
+    skip_empty: bool,
+    max_frame_len: usize,
+    progress: Progress,
+}
+
+/// Frames encoded with the Serial Line Internet Protocol (SLIP, RFC 1055).
+///
+/// Frames are encoded with an `END` byte both before and after them, and empty frames are
+/// skipped while decoding. A frame that cannot be decoded is discarded up to and
+/// including its `END` byte.
+#[derive(Clone, Copy, Debug)]
+pub struct Slip {
+    max_frame_len: usize,
+    progress: Progress,
+}
+
+/// Frames encoded with Consistent Overhead Byte Stuffing (COBS), each followed by a zero
+/// byte.
+///
+/// Consecutive zero bytes are ignored while decoding. A frame that cannot be decoded is
+/// discarded up to and including its zero byte.
+#[derive(Clone, Copy, Debug)]
+pub struct Cobs {
+    max_frame_len: usize,
+    progress: Progress,
+}
+
+/// How far decoding a delimited frame has got, kept between calls to `decode` so that the
+/// received bytes are only searched once.
+#[derive(Clone, Copy, Debug, Default)]
+struct Progress {
+    /// The number of leading bytes that are not part of the frame.
+    start: usize,
+    /// The index from which to continue searching for a delimiter.
+    next_index: usize,
+    /// Whether the bytes up to the next delimiter are the rest of an invalid frame.
+    discarding: bool,
+    /// Whether the last line ended with a `\r`, so that a leading `\n` completes its
+    /// line ending.
+    skip_lf: bool,
+}
+
+/// Sends and receives frames over a byte stream.
+///
+/// Frames are received with [`recv`], or by iterating over the `Framed`, and sent with
+/// [`send`].
+///
+/// [`recv`]: #method.recv
+/// [`send`]: #method.send
+#[derive(Debug)]
+pub struct Framed<T, F> {
+    inner: T,
+    framing: F,
+    buf: Vec<u8>,
+    eof: bool,
+}
+
+fn frame_too_long() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "frame exceeds the maximum length",
+    )
+}
+
+impl Progress {
+    /// Returns the index of the next delimiter in `src`, continuing the previous search.
+    fn find<P: Fn(u8) -> bool>(&mut self, src: &[u8], is_delimiter: P) -> Option<usize> {
+        match src[self.next_index..].iter().position(|&b| is_delimiter(b)) {
+            Some(i) => {
+                self.next_index += i + 1;
+                Some(self.next_index - 1)
+            }
+            None => {
+                self.next_index = src.len();
+                None
+            }
+        }
+    }
+
+    /// Marks the first `len` bytes of `src` as an invalid frame, returning `err`.
+    ///
+    /// If the frame has not been delimited yet, the bytes up to its delimiter are
+    /// discarded as they arrive.
+    fn fail(&mut self, len: usize, delimited: bool, err: io::Error) -> io::Error {
+        self.start = len;
+        self.next_index = len;
+        self.discarding = !delimited;
+        err
+    }
+
+    fn recover(&mut self, src: &[u8]) -> usize {
+        let len = self.start.min(src.len());
+        self.start = 0;
+        self.next_index = 0;
+        len
+    }
+
+    fn reset(&mut self) {
+        *self = Progress::default();
+    }
+}
+
+impl LengthDelimited {
+    /// Creates a new length-delimited framing with the default settings.
+    pub fn new() -> Self {
+        Self {
+            header_len: 4,
+            endianness: Endianness::Big,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+        }
+    }
+
+    /// Sets the number of bytes of the length header.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is not between 1 and 8.
+    pub fn header_len(&mut self, len: usize) -> &mut Self {
+        assert!(
+            (1..=8).contains(&len),
+            "length header must be between 1 and 8 bytes"
+        );
+        self.header_len = len;
+        self
+    }
+
+    /// Sets the byte order of the length header.
+    pub fn endianness(&mut self, endianness: Endianness) -> &mut Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Sets the maximum length of a frame, excluding the header.
+    pub fn max_frame_len(&mut self, len: usize) -> &mut Self {
+        self.max_frame_len = len;
+        self
+    }
+}
+
+impl Default for LengthDelimited {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Framing for LengthDelimited {
+    fn decode(&mut self, src: &[u8]) -> io::Result<Option<(Vec<u8>, usize)>> {
+        if src.len() < self.header_len {
+            return Ok(None);
+        }
+        let header = &src[..self.header_len];
+        let len = match self.endianness {
+            Endianness::Big => header.iter().fold(0, |len, &b| len << 8 | u64::from(b)),
+            Endianness::Little => header
+                .iter()
+                .rev()
+                .fold(0, |len, &b| len << 8 | u64::from(b)),
+        };
+        let len = match usize::try_from(len) {
+            Ok(len) if len <= self.max_frame_len => len,
+            _ => return Err(frame_too_long()),
+        };
+
+        let end = self.header_len + len;
+        if src.len() < end {
+            return Ok(None);
+        }
+        Ok(Some((src[self.header_len..end].to_vec(), end)))
+    }
+
+    fn encode(&mut self, frame: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        let len = frame.len() as u64;
+        if frame.len() > self.max_frame_len
+            || (self.header_len < 8 && len >> (8 * self.header_len) != 0)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frame is too long to be encoded",
+            ));
+        }
+        match self.endianness {
+            Endianness::Big => dst.extend_from_slice(&len.to_be_bytes()[8 - self.header_len..]),
+            Endianness::Little => dst.extend_from_slice(&len.to_le_bytes()[..self.header_len]),
+        }
+        dst.extend_from_slice(frame);
+        Ok(())
+    }
+}
+
+impl LineDelimited {
+    /// Creates a new line-delimited framing with the default settings.
+    pub fn new() -> Self {
+        Self {
+            ending: LineEnding::CrLf,
+            skip_empty: false,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+            progress: Progress::default(),
+        }
+    }
+
+    /// Sets the line ending that encoded frames are followed by.
+    pub fn ending(&mut self, ending: LineEnding) -> &mut Self {
+        self.ending = ending;
+        self
+    }
+
+    /// Sets whether empty lines are skipped while decoding.
+    ///
+    /// AT responses are both preceded and followed by `\r\n`, so skipping empty lines
+    /// yields only their contents.
+    pub fn skip_empty(&mut self, skip: bool) -> &mut Self {
+        self.skip_empty = skip;
+        self
+    }
+
+    /// Sets the maximum length of a line, excluding its line ending.
+    pub fn max_frame_len(&mut self, len: usize) -> &mut Self {
+        self.max_frame_len = len;
+        self
+    }
+
+    fn decode_line(&mut self, src: &[u8], eof: bool) -> io::Result<Option<(Vec<u8>, usize)>> {
+        let progress = &mut self.progress;
+        loop {
+            if progress.skip_lf && progress.start < src.len() {
+                progress.skip_lf = false;
+                if src[progress.start] == b'\n' {
+                    progress.start += 1;
+                    progress.next_index = progress.start;
+                }
+            }
+
+            let start = progress.start;
+            let end = match progress.find(src, |b| b == b'\n' || b == b'\r') {
+                Some(end) => end,
+                None if src.len() - start > self.max_frame_len => {
+                    return Err(progress.fail(src.len(), false, frame_too_long()))
+                }
+                None if eof => {
+                    let line = (!progress.discarding && start < src.len())
+                        .then(|| (src[start..].to_vec(), src.len()));
+                    progress.reset();
+                    return Ok(line);
+                }
+                None => return Ok(None),
+            };
+
+            let (consumed, skip_lf) = match (src[end], src.get(end + 1)) {
+                (b'\r', Some(b'\n')) => (end + 2, false),
+                // The line feed of a `\r\n` may not have been received yet.
+                (b'\r', None) => (end + 1, !eof),
+                _ => (end + 1, false),
+            };
+            progress.next_index = consumed;
+            progress.skip_lf = skip_lf;
+            if progress.discarding || (end == start && self.skip_empty) {
+                progress.discarding = false;
+                progress.start = consumed;
+                continue;
+            }
+            if end - start > self.max_frame_len {
+                return Err(progress.fail(consumed, true, frame_too_long()));
+            }
+            progress.reset();
+            progress.skip_lf = skip_lf;
+            return Ok(Some((src[start..end].to_vec(), consumed)));
+        }
+    }
+}
+
+impl Default for LineDelimited {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Framing for LineDelimited {
+    fn decode(&mut self, src: &[u8]) -> io::Result<Option<(Vec<u8>, usize)>> {
+        self.decode_line(src, false)
+    }
+
+    fn decode_eof(&mut self, src: &[u8]) -> io::Result<Option<(Vec<u8>, usize)>> {
+        self.decode_line(src, true)
+    }
+
+    fn recover(&mut self, src: &[u8]) -> usize {
+        self.progress.recover(src)
+    }
+
+    fn encode(&mut self, frame: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        if frame.iter().any(|&b| b == b'\n' || b == b'\r') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frame contains a line ending",
+            ));
+        }
+        dst.extend_from_slice(frame);
+        dst.extend_from_slice(match self.ending {
+            LineEnding::Lf => b"\n",
+            LineEnding::Cr => b"\r",
+            LineEnding::CrLf => b"\r\n",
+        });
+        Ok(())
+    }
+}
+
+const SLIP_END: u8 = 0xc0;
+const SLIP_ESC: u8 = 0xdb;
+const SLIP_ESC_END: u8 = 0xdc;
+const SLIP_ESC_ESC: u8 = 0xdd;
+
+impl Slip {
+    /// Creates a new SLIP framing with the default settings.
+    pub fn new() -> Self {
+        Self {
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+            progress: Progress::default(),
+        }
+    }
+
+    /// Sets the maximum length of a decoded frame.
+    pub fn max_frame_len(&mut self, len: usize) -> &mut Self {
+        self.max_frame_len = len;
+        self
+    }
+}
+
+impl Default for Slip {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Framing for Slip {
+    fn decode(&mut self, src: &[u8]) -> io::Result<Option<(Vec<u8>, usize)>> {
+        let progress = &mut self.progress;
+        let end = loop {
+            match progress.find(src, |b| b == SLIP_END) {
+                Some(end) if progress.discarding || end == progress.start => {
+                    progress.discarding = false;
+                    progress.start = end + 1;
+                }
+                Some(end) => break end,
+                // Escaping at most doubles the length of a frame.
+                None if src.len() - progress.start > self.max_frame_len.saturating_mul(2) => {
+                    return Err(progress.fail(src.len(), false, frame_too_long()))
+                }
+                None => return Ok(None),
+            }
+        };
+
+        let mut frame = Vec::with_capacity(end - progress.start);
+        let mut bytes = src[progress.start..end].iter();
+        while let Some(&b) = bytes.next() {
+            frame.push(match b {
+                SLIP_ESC => match bytes.next() {
+                    Some(&SLIP_ESC_END) => SLIP_END,
+                    Some(&SLIP_ESC_ESC) => SLIP_ESC,
+                    _ => {
+                        let err = io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "invalid SLIP escape sequence",
+                        );
+                        return Err(progress.fail(end + 1, true, err));
+                    }
+                },
+                b => b,
+            });
+        }
+        if frame.len() > self.max_frame_len {
+            return Err(progress.fail(end + 1, true, frame_too_long()));
+        }
+        progress.reset();
+        Ok(Some((frame, end + 1)))
+    }
+
+    fn decode_eof(&mut self, src: &[u8]) -> io::Result<Option<(Vec<u8>, usize)>> {
+        match self.decode(src)? {
+            // Trailing `END` bytes only delimit empty frames.
+            None if self.progress.discarding || self.progress.start == src.len() => {
+                self.progress.reset();
+                Ok(None)
+            }
+            None => {
+                let err =
+                    io::Error::new(io::ErrorKind::UnexpectedEof, "stream ended within a frame");
+                Err(self.progress.fail(src.len(), true, err))
+            }
+            frame => Ok(frame),
+        }
+    }
+
+    fn recover(&mut self, src: &[u8]) -> usize {
+        self.progress.recover(src)
+    }
+
+    fn encode(&mut self, frame: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        dst.reserve(frame.len() + 2);
+        dst.push(SLIP_END);
+        for &b in frame {
+            match b {
+                SLIP_END => dst.extend_from_slice(&[SLIP_ESC, SLIP_ESC_END]),
+                SLIP_ESC => dst.extend_from_slice(&[SLIP_ESC, SLIP_ESC_ESC]),
+                b => dst.push(b),
+            }
+        }
+        dst.push(SLIP_END);
+        Ok(())
+    }
+}
+
+impl Cobs {
+    /// Creates a new COBS framing with the default settings.
+    pub fn new() -> Self {
+        Self {
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+            progress: Progress::default(),
+        }
+    }
+
+    /// Sets the maximum length of a decoded frame.
+    pub fn max_frame_len(&mut self, len: usize) -> &mut Self {
+        self.max_frame_len = len;
+        self
+    }
+}
+
+impl Default for Cobs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Framing for Cobs {
+    fn decode(&mut self, src: &[u8]) -> io::Result<Option<(Vec<u8>, usize)>> {
+        let progress = &mut self.progress;
+        let max_encoded_len = self
+            .max_frame_len
+            .saturating_add(self.max_frame_len / 254 + 1);
+        let end = loop {
+            match progress.find(src, |b| b == 0) {
+                Some(end) if progress.discarding || end == progress.start => {
+                    progress.discarding = false;
+                    progress.start = end + 1;
+                }
+                Some(end) => break end,
+                // Encoding adds one byte per 254 bytes of a frame, plus one.
+                None if src.len() - progress.start > max_encoded_len => {
+                    return Err(progress.fail(src.len(), false, frame_too_long()))
+                }
+                None => return Ok(None),
+            }
+        };
+
+        let encoded = &src[progress.start..end];
+        let mut frame = Vec::with_capacity(encoded.len());
+        let mut i = 0;
+        while i < encoded.len() {
+            let code = usize::from(encoded[i]);
+            let block = match encoded.get(i + 1..i + code) {
+                Some(block) => block,
+                None => {
+                    let err = io::Error::new(io::ErrorKind::InvalidData, "invalid COBS encoding");
+                    return Err(progress.fail(end + 1, true, err));
+                }
+            };
+            frame.extend_from_slice(block);
+            i += code;
+            if code < 0xff && i < encoded.len() {
+                frame.push(0);
+            }
+        }
+        if frame.len() > self.max_frame_len {
+            return Err(progress.fail(end + 1, true, frame_too_long()));
+        }
+        progress.reset();
+        Ok(Some((frame, end + 1)))
+    }
+
+    fn decode_eof(&mut self, src: &[u8]) -> io::Result<Option<(Vec<u8>, usize)>> {
+        match self.decode(src)? {
+            // Trailing zero bytes only delimit empty frames.
+            None if self.progress.discarding || self.progress.start == src.len() => {
+                self.progress.reset();
+                Ok(None)
+            }
+            None => {
+                let err =
+                    io::Error::new(io::ErrorKind::UnexpectedEof, "stream ended within a frame");
+                Err(self.progress.fail(src.len(), true, err))
+            }
+            frame => Ok(frame),
+        }
+    }
+
+    fn recover(&mut self, src: &[u8]) -> usize {
+        self.progress.recover(src)
+    }
+
+    fn encode(&mut self, frame: &[u8], dst: &mut Vec<u8>) -> io::Result<()> {
+        dst.reserve(frame.len() + frame.len() / 254 + 2);
+        let mut code_idx = dst.len();
+        dst.push(0);
+        let mut code = 1u8;
+        for &b in frame {
+            // A full block is only closed once more bytes follow it.
+            if b == 0 || code == 0xff {
+                dst[code_idx] = code;
+                code_idx = dst.len();
+                dst.push(0);
+                code = 1;
+            }
+            if b != 0 {
+                dst.push(b);
+                code += 1;
+            }
+        }
+        dst[code_idx] = code;
+        dst.push(0);
+        Ok(())
+    }
+}
+
+impl<T, F> Framed<T, F> {
+    /// Creates a new `Framed` which sends and receives frames over `inner`.
+    pub fn new(inner: T, framing: F) -> Self {
+        Self {
+            inner,
+            framing,
+            buf: Vec::new(),
+            eof: false,
+        }
+    }
+
+    /// Gets a reference to the underlying stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying stream.
+    ///
+    /// Reading from the stream directly may corrupt the frames being received.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consumes this `Framed`, returning the underlying stream.
+    ///
+    /// Any bytes received but not yet decoded are lost.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Read, F: Framing> Framed<T, F> {
+    /// Receives the next frame, blocking until it has arrived.
+    ///
+    /// Returns `None` once the stream has ended. If a frame cannot be decoded, an error
+    /// is returned and the bytes the framing cannot recover from are discarded: the
+    /// delimited framings only discard the invalid frame, so that the frames after it
+    /// can still be received.
+    pub fn recv(&mut self) -> io::Result<Option<Vec<u8>>> {
+        loop {
+            let frame = if self.eof {
+                self.framing.decode_eof(&self.buf)
+            } else {
+                self.framing.decode(&self.buf)
+            };
+            let frame = frame.inspect_err(|_| {
+                let len = self.framing.recover(&self.buf);
+                self.buf.drain(..len);
+            })?;
+            if let Some((frame, len)) = frame {
+                self.buf.drain(..len);
+                return Ok(Some(frame));
+            }
+            if self.eof {
+                self.buf.clear();
+                return Ok(None);
+            }
+
+            let len = self.buf.len();
+            self.buf.resize(len + READ_CHUNK_LEN, 0);
+            let n = match self.inner.read(&mut self.buf[len..]) {
+                Ok(n) => n,
+                Err(e) => {
+                    self.buf.truncate(len);
+                    if e.kind() == io::ErrorKind::Interrupted {
+                        continue;
+                    }
+                    return Err(e);
+                }
+            };
+            self.buf.truncate(len + n);
+            self.eof = n == 0;
+        }
+    }
+}
+
+impl<T: Write, F: Framing> Framed<T, F> {
+    /// Sends a frame, blocking until all of it has been written.
+    pub fn send(&mut self, frame: &[u8]) -> io::Result<()> {
+        let mut buf = Vec::new();
+        self.framing.encode(frame, &mut buf)?;
+        self.inner.write_all(&buf)
+    }
+}
+
+impl<T: Read, F: Framing> Iterator for Framed<T, F> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<io::Result<Vec<u8>>> {
+        self.recv().transpose()
+    }
+}
+
+#[cfg(feature = "tokio-util")]
+mod codec {
+    use super::*;
+    use bytes::{Buf, BytesMut};
+    use tokio_util::codec::{Decoder, Encoder};
+
+    macro_rules! impl_codec {
+        ($($t:ty),*) => {$(
+            impl Decoder for $t {
+                type Item = Vec<u8>;
+                type Error = io::Error;
+
+                fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Vec<u8>>> {
+                    let frame = Framing::decode(self, &src[..]).inspect_err(|_| {
+                        src.advance(Framing::recover(self, &src[..]));
+                    })?;
+                    Ok(frame.map(|(frame, len)| {
+                        src.advance(len);
+                        frame
+                    }))
+                }
+
+                fn decode_eof(&mut self, src: &mut BytesMut) -> io::Result<Option<Vec<u8>>> {
+                    let frame = Framing::decode_eof(self, &src[..]).inspect_err(|_| {
+                        src.advance(Framing::recover(self, &src[..]));
+                    })?;
+                    if frame.is_none() {
+                        src.clear();
+                    }
+                    Ok(frame.map(|(frame, len)| {
+                        src.advance(len);
+                        frame
+                    }))
+                }
+            }
+
+            impl<T: AsRef<[u8]>> Encoder<T> for $t {
+                type Error = io::Error;
+
+                fn encode(&mut self, frame: T, dst: &mut BytesMut) -> io::Result<()> {
+                    let mut buf = Vec::new();
+                    Framing::encode(self, frame.as_ref(), &mut buf)?;
+                    dst.extend_from_slice(&buf);
+                    Ok(())
+                }
+            }
+        )*};
+    }
+
+    impl_codec!(LengthDelimited, LineDelimited, Slip, Cobs);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// A stream which returns each of its chunks from a separate read.
+    struct Chunks(VecDeque<Vec<u8>>);
+
+    impl Read for Chunks {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let chunk = match self.0.front_mut() {
+                Some(chunk) => chunk,
+                None => return Ok(0),
+            };
+            let n = chunk.len().min(buf.len());
+            buf[..n].copy_from_slice(&chunk[..n]);
+            chunk.drain(..n);
+            if chunk.is_empty() {
+                self.0.pop_front();
+            }
+            Ok(n)
+        }
+    }
+
+    /// A stream which returns its chunks and then blocks, like a device awaiting a reply.
+    struct Pending(Chunks);
+
+    impl Read for Pending {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if (self.0).0.is_empty() {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            self.0.read(buf)
+        }
+    }
+
+    fn recv_all<F: Framing>(framing: F, chunks: &[&[u8]]) -> Vec<io::Result<Vec<u8>>> {
+        let chunks = chunks.iter().map(|chunk| chunk.to_vec()).collect();
+        Framed::new(Chunks(chunks), framing).collect()
+    }
+
+    fn frames<F: Framing>(framing: F, chunks: &[&[u8]]) -> Vec<Vec<u8>> {
+        recv_all(framing, chunks)
+            .into_iter()
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    fn encode<F: Framing>(framing: &mut F, frames: &[Vec<u8>]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for frame in frames {
+            framing.encode(frame, &mut buf).unwrap();
+        }
+        buf
+    }
+
+    fn assert_round_trip<F: Framing + Copy>(mut framing: F, expected: &[Vec<u8>]) {
+        let encoded = encode(&mut framing, expected);
+        assert_eq!(frames(framing, &[&encoded]), expected);
+
+        let bytes: Vec<&[u8]> = encoded.chunks(1).collect();
+        assert_eq!(frames(framing, &bytes), expected);
+    }
+
+    fn binary_frames() -> Vec<Vec<u8>> {
+        vec![
+            b"hello".to_vec(),
+            vec![0],
+            vec![SLIP_END, SLIP_ESC, SLIP_ESC_END, SLIP_ESC_ESC],
+            (1..=255).collect(),
+            vec![0xff; 255],
+        ]
+    }
+
+    fn is_invalid_data(result: &io::Result<Vec<u8>>) -> bool {
+        matches!(result, Err(e) if e.kind() == io::ErrorKind::InvalidData)
+    }
+
+    #[test]
+    fn length_delimited_round_trip() {
+        let mut frames = binary_frames();
+        frames.push(Vec::new());
+        for header_len in 1..=8 {
+            for &endianness in &[Endianness::Big, Endianness::Little] {
+                let framing = *LengthDelimited::new()
+                    .header_len(header_len)
+                    .endianness(endianness);
+                assert_round_trip(framing, &frames);
+            }
+        }
+    }
+
+    #[test]
+    fn length_delimited_header() {
+        let mut framing = *LengthDelimited::new().header_len(3);
+        assert_eq!(encode(&mut framing, &[vec![7; 2]]), [0, 0, 2, 7, 7]);
+
+        framing.endianness(Endianness::Little);
+        assert_eq!(encode(&mut framing, &[vec![7; 2]]), [2, 0, 0, 7, 7]);
+
+        let mut framing = *LengthDelimited::new().header_len(1);
+        let mut buf = Vec::new();
+        assert!(framing.encode(&[0; 256], &mut buf).is_err());
+    }
+
+    #[test]
+    fn line_delimited_round_trip() {
+        let frames = vec![b"AT+CIND?".to_vec(), Vec::new(), b"OK".to_vec()];
+        for &ending in &[LineEnding::Lf, LineEnding::Cr, LineEnding::CrLf] {
+            assert_round_trip(*LineDelimited::new().ending(ending), &frames);
+        }
+    }
+
+    #[test]
+    fn line_delimited_split_delivery() {
+        let framing = LineDelimited::new();
+        assert_eq!(
+            frames(framing, &[b"AT\r", b"\nOK\r", b"\n"]),
+            [b"AT".to_vec(), b"OK".to_vec()]
+        );
+        assert_eq!(
+            frames(framing, &[b"A", b"T", b"\r", b"\r", b"\n"]),
+            [b"AT".to_vec(), Vec::new()]
+        );
+        assert_eq!(frames(framing, &[b"AT\r"]), [b"AT".to_vec()]);
+        assert_eq!(
+            frames(framing, &[b"AT\n", b"OK"]),
+            [b"AT".to_vec(), b"OK".to_vec()]
+        );
+
+        let framing = *LineDelimited::new().skip_empty(true);
+        assert_eq!(
+            frames(framing, &[b"\r\n+CIND: 1\r", b"\n\r\nOK\r\n"]),
+            [b"+CIND: 1".to_vec(), b"OK".to_vec()]
+        );
+    }
+
+    #[test]
+    fn line_delimited_cr_without_eof() {
+        let chunks = vec![b"AT+BRSF=1\r".to_vec()].into();
+        let mut framed = Framed::new(Pending(Chunks(chunks)), LineDelimited::new());
+        assert_eq!(framed.recv().unwrap(), Some(b"AT+BRSF=1".to_vec()));
+        assert_eq!(framed.recv().unwrap_err().kind(), io::ErrorKind::WouldBlock);
+
+        let chunks = vec![b"AT\r".to_vec(), b"\nOK\r".to_vec(), b"\n".to_vec()].into();
+        let mut framed = Framed::new(Pending(Chunks(chunks)), LineDelimited::new());
+        assert_eq!(framed.recv().unwrap(), Some(b"AT".to_vec()));
+        assert_eq!(framed.recv().unwrap(), Some(b"OK".to_vec()));
+        assert_eq!(framed.recv().unwrap_err().kind(), io::ErrorKind::WouldBlock);
+    }
+
+    #[test]
+    fn slip_round_trip() {
+        assert_round_trip(Slip::new(), &binary_frames());
+    }
+
+    #[test]
+    fn slip_invalid_escape() {
+        let results = recv_all(
+            Slip::new(),
+            &[&[SLIP_END, SLIP_ESC, 1, SLIP_END, b'a', SLIP_END]],
+        );
+        assert_eq!(results.len(), 2);
+        assert!(is_invalid_data(&results[0]));
+        assert_eq!(results[1].as_ref().unwrap(), b"a");
+    }
+
+    #[test]
+    fn cobs_round_trip() {
+        let mut frames = binary_frames();
+        frames.push(Vec::new());
+        frames.push(vec![1; 254]);
+        frames.push(vec![0; 3]);
+        assert_round_trip(Cobs::new(), &frames);
+    }
+
+    #[test]
+    fn cobs_encoding() {
+        let mut framing = Cobs::new();
+        assert_eq!(encode(&mut framing, &[Vec::new()]), [1, 0]);
+        assert_eq!(encode(&mut framing, &[vec![0]]), [1, 1, 0]);
+        assert_eq!(
+            encode(&mut framing, &[vec![0x11, 0x22, 0, 0x33]]),
+            [3, 0x11, 0x22, 2, 0x33, 0]
+        );
+
+        let run: Vec<u8> = (1..=254).collect();
+        let mut encoded = vec![0xff];
+        encoded.extend_from_slice(&run);
+        encoded.push(0);
+        assert_eq!(encode(&mut framing, std::slice::from_ref(&run)), encoded);
+
+        let mut longer = run;
+        longer.push(0xff);
+        encoded.pop();
+        encoded.extend_from_slice(&[2, 0xff, 0]);
+        assert_eq!(encode(&mut framing, &[longer]), encoded);
+    }
+
+    #[test]
+    fn cobs_invalid_encoding() {
+        let results = recv_all(Cobs::new(), &[&[5, 1, 0, 2, b'a', 0]]);
+        assert_eq!(results.len(), 2);
+        assert!(is_invalid_data(&results[0]));
+        assert_eq!(results[1].as_ref().unwrap(), b"a");
+    }
+
+    #[test]
+    fn max_frame_len() {
+        let results = recv_all(*LengthDelimited::new().max_frame_len(4), &[&[0, 0, 0, 5]]);
+        assert_eq!(results.len(), 1);
+        assert!(is_invalid_data(&results[0]));
+
+        let framing = *LineDelimited::new().max_frame_len(4);
+        let results = recv_all(framing, &[b"toolong\nok\n"]);
+        assert!(is_invalid_data(&results[0]));
+        assert_eq!(results[1].as_ref().unwrap(), b"ok");
+        assert_eq!(results.len(), 2);
+
+        // The rest of a line that is too long is discarded as it arrives.
+        let results = recv_all(framing, &[b"toolo", b"ng\r\nok\r\n"]);
+        assert!(is_invalid_data(&results[0]));
+        assert_eq!(results[1].as_ref().unwrap(), b"ok");
+        assert_eq!(results.len(), 2);
+
+        let mut framing = *Slip::new().max_frame_len(4);
+        let mut encoded = encode(&mut framing, &[b"toolong".to_vec()]);
+        encoded.extend(encode(&mut Slip::new(), &[b"ok".to_vec()]));
+        let results = recv_all(framing, &[&encoded]);
+        assert!(is_invalid_data(&results[0]));
+        assert_eq!(results[1].as_ref().unwrap(), b"ok");
+        assert_eq!(results.len(), 2);
+
+        let framing = *Cobs::new().max_frame_len(4);
+        let mut encoded = encode(&mut Cobs::new(), &[b"toolongtoolong".to_vec()]);
+        let (first, rest) = encoded.split_at(8);
+        let (first, rest) = (first.to_vec(), rest.to_vec());
+        encoded = rest;
+        encoded.extend(encode(&mut Cobs::new(), &[b"ok".to_vec()]));
+        let results = recv_all(framing, &[&first, &encoded]);
+        assert!(is_invalid_data(&results[0]));
+        assert_eq!(results[1].as_ref().unwrap(), b"ok");
+        assert_eq!(results.len(), 2);
+    }
+}
@@ -0,0 +1,514 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use crate::sys::bt as sys;
+use crate::sys_common::bt as bt_imp;
+use crate::sys_common::{AsInner, FromInner, IntoInner};
+
+// The largest HCI frame, plus the packet type indicator.
+const MAX_PACKET_SIZE: usize = 1 + 1500;
+
+/// The type of an HCI packet, as given by its packet type indicator.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum HciPacketType {
+    /// A command sent to the controller, indicator 0x01.
+    Command,
+    /// Asynchronous connection-oriented data, indicator 0x02.
+    AclData,
+    /// Synchronous connection-oriented data, indicator 0x03.
+    ScoData,
+    /// An event reported by the controller, indicator 0x04.
+    Event,
+    /// Isochronous data, indicator 0x05.
+    IsoData,
+    /// A vendor-specific packet, indicator 0xff.
+    Vendor,
+}
+
+impl From<HciPacketType> for u8 {
+    fn from(ty: HciPacketType) -> u8 {
+        match ty {
+            HciPacketType::Command => 0x01,
+            HciPacketType::AclData => 0x02,
+            HciPacketType::ScoData => 0x03,
+            HciPacketType::Event => 0x04,
+            HciPacketType::IsoData => 0x05,
+            HciPacketType::Vendor => 0xff,
+        }
+    }
+}
+
+impl HciPacketType {
+    fn from_indicator(raw: u8) -> Option<Self> {
+        match raw {
+            0x01 => Some(HciPacketType::Command),
+            0x02 => Some(HciPacketType::AclData),
+            0x03 => Some(HciPacketType::ScoData),
+            0x04 => Some(HciPacketType::Event),
+            0x05 => Some(HciPacketType::IsoData),
+            0xff => Some(HciPacketType::Vendor),
+            _ => None,
+        }
+    }
+
+    // The bit of this packet type in the filter's type mask, which the kernel takes from
+    // the low five bits of the indicator.
+    fn filter_bit(self) -> u32 {
+        u32::from(u8::from(self) & 31)
+    }
+}
+
+/// A filter selecting the packets received by an [`HciSocket`].
+///
+/// A packet passes the filter if its type is set, and for events, if its event code is
+/// set too. A new filter passes no packets.
+///
+/// [`HciSocket`]: struct.HciSocket.html
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct HciFilter {
+    type_mask: u32,
+    event_mask: [u32; 2],
+    opcode: u16,
+}
+
+impl HciFilter {
+    /// Creates a new filter which passes no packets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Passes packets of type `ty`.
+    pub fn set_packet_type(&mut self, ty: HciPacketType) -> &mut Self {
+        self.type_mask |= 1 << ty.filter_bit();
+        self
+    }
+
+    /// Stops passing packets of type `ty`.
+    pub fn clear_packet_type(&mut self, ty: HciPacketType) -> &mut Self {
+        self.type_mask &= !(1 << ty.filter_bit());
+        self
+    }
+
+    /// Passes packets of all types.
+    pub fn all_packet_types(&mut self) -> &mut Self {
+        self.type_mask = !0;
+        self
+    }
+
+    /// Returns whether packets of type `ty` are passed.
+    pub fn has_packet_type(&self, ty: HciPacketType) -> bool {
+        self.type_mask & (1 << ty.filter_bit()) != 0
+    }
+
+    /// Passes events with the event code `event`.
+    pub fn set_event(&mut self, event: u8) -> &mut Self {
+        let (word, bit) = event_bit(event);
+        self.event_mask[word] |= bit;
+        self
+    }
+
+    /// Stops passing events with the event code `event`.
+    pub fn clear_event(&mut self, event: u8) -> &mut Self {
+        let (word, bit) = event_bit(event);
+        self.event_mask[word] &= !bit;
+        self
+    }
+
+    /// Passes events with any event code.
+    pub fn all_events(&mut self) -> &mut Self {
+        self.event_mask = [!0; 2];
+        self
+    }
+
+    /// Returns whether events with the event code `event` are passed.
+    pub fn has_event(&self, event: u8) -> bool {
+        let (word, bit) = event_bit(event);
+        self.event_mask[word] & bit != 0
+    }
+
+    /// Only passes Command Complete and Command Status events for the command `opcode`.
+    ///
+    /// An opcode of 0 passes these events for all commands.
+    pub fn set_opcode(&mut self, opcode: u16) -> &mut Self {
+        self.opcode = opcode;
+        self
+    }
+
+    /// Returns the command opcode that Command Complete and Command Status events are
+    /// restricted to, or 0 if they are not restricted.
+    pub fn opcode(&self) -> u16 {
+        self.opcode
+    }
+}
+
+fn event_bit(event: u8) -> (usize, u32) {
+    let event = event & 63;
+    (usize::from(event >> 5), 1 << (event & 31))
+}
+
+/// An HCI packet sent to or received from a controller.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum HciPacket {
+    /// A command sent to the controller.
+    Command { opcode: u16, params: Vec<u8> },
+    /// An event reported by the controller.
+    Event { code: u8, params: Vec<u8> },
+    /// ACL data, with the packet boundary and broadcast flags in the low four bits of
+    /// `flags`.
+    AclData {
+        handle: u16,
+        flags: u8,
+        data: Vec<u8>,
+    },
+    /// Any other packet, with its payload following the packet type indicator.
+    Other { ty: HciPacketType, payload: Vec<u8> },
+}
+
+impl HciPacket {
+    /// Creates a command packet from its opcode group (OGF) and command (OCF) fields.
+    pub fn command(ogf: u8, ocf: u16, params: Vec<u8>) -> HciPacket {
+        HciPacket::Command {
+            opcode: (u16::from(ogf) << 10) | (ocf & 0x03ff),
+            params,
+        }
+    }
+
+    /// Returns the type of this packet.
+    pub fn packet_type(&self) -> HciPacketType {
+        match *self {
+            HciPacket::Command { .. } => HciPacketType::Command,
+            HciPacket::Event { .. } => HciPacketType::Event,
+            HciPacket::AclData { .. } => HciPacketType::AclData,
+            HciPacket::Other { ty, .. } => ty,
+        }
+    }
+
+    fn encode(&self) -> io::Result<Vec<u8>> {
+        let mut buf = vec![u8::from(self.packet_type())];
+        match *self {
+            HciPacket::Command { opcode, ref params } => {
+                buf.extend_from_slice(&opcode.to_le_bytes());
+                buf.push(param_len(params.len())?);
+                buf.extend_from_slice(params);
+            }
+            HciPacket::Event { code, ref params } => {
+                buf.push(code);
+                buf.push(param_len(params.len())?);
+                buf.extend_from_slice(params);
+            }
+            HciPacket::AclData {
+                handle,
+                flags,
+                ref data,
+            } => {
+                let len = u16::try_from(data.len()).map_err(|_| too_long())?;
+                let handle = (handle & 0x0fff) | (u16::from(flags & 0x0f) << 12);
+                buf.extend_from_slice(&handle.to_le_bytes());
+                buf.extend_from_slice(&len.to_le_bytes());
+                buf.extend_from_slice(data);
+            }
+            HciPacket::Other { ref payload, .. } => buf.extend_from_slice(payload),
+        }
+        Ok(buf)
+    }
+
+    fn decode(buf: &[u8]) -> io::Result<HciPacket> {
+        let (&indicator, rest) = buf.split_first().ok_or_else(malformed)?;
+        let ty = HciPacketType::from_indicator(indicator).ok_or_else(malformed)?;
+        let packet = match ty {
+            HciPacketType::Command if rest.len() >= 3 => HciPacket::Command {
+                opcode: u16::from_le_bytes([rest[0], rest[1]]),
+                params: payload(&rest[3..], usize::from(rest[2]))?,
+            },
+            HciPacketType::Event if rest.len() >= 2 => HciPacket::Event {
+                code: rest[0],
+                params: payload(&rest[2..], usize::from(rest[1]))?,
+            },
+            HciPacketType::AclData if rest.len() >= 4 => {
+                let handle = u16::from_le_bytes([rest[0], rest[1]]);
+                HciPacket::AclData {
+                    handle: handle & 0x0fff,
+                    flags: (handle >> 12) as u8,
+                    data: payload(
+                        &rest[4..],
+                        usize::from(u16::from_le_bytes([rest[2], rest[3]])),
+                    )?,
+                }
+            }
+            HciPacketType::Command | HciPacketType::Event | HciPacketType::AclData => {
+                return Err(malformed())
+            }
+            ty => HciPacket::Other {
+                ty,
+                payload: rest.to_vec(),
+            },
+        };
+        Ok(packet)
+    }
+}
+
+fn param_len(len: usize) -> io::Result<u8> {
+    u8::try_from(len).map_err(|_| too_long())
+}
+
+fn payload(buf: &[u8], len: usize) -> io::Result<Vec<u8>> {
+    buf.get(..len).map(|p| p.to_vec()).ok_or_else(malformed)
+}
+
+fn too_long() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "HCI packet payload is too long",
+    )
+}
+
+fn malformed() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "malformed HCI packet")
+}
+
+/// A raw HCI socket, bound to a local adapter.
+///
+/// Raw HCI sockets allow sending arbitrary commands to a controller and observing the
+/// events and data it reports. Which packets are received is controlled by the socket's
+/// [`HciFilter`]. Sending commands requires the `CAP_NET_RAW` capability.
+///
+/// The socket will be closed when the value is dropped.
+///
+/// [`HciFilter`]: struct.HciFilter.html
+pub struct HciSocket(bt_imp::HciSocket);
+
+impl HciSocket {
+    /// Opens a raw HCI socket bound to the adapter with the device ID `dev_id`, e.g. 0
+    /// for `hci0`.
+    pub fn open(dev_id: u16) -> io::Result<Self> {
+        bt_imp::HciSocket::open(dev_id).map(HciSocket)
+    }
+
+    /// Opens a raw HCI socket bound to the first available adapter.
+    pub fn open_default() -> io::Result<Self> {
+        Self::open(sys::default_hci_dev()?)
+    }
+
+    /// Returns the device ID of the adapter this socket is bound to.
+    pub fn dev_id(&self) -> u16 {
+        self.0.dev_id()
+    }
+
+    /// Sets the filter selecting the packets received on this socket.
+    pub fn set_filter(&self, filter: &HciFilter) -> io::Result<()> {
+        self.0
+            .socket()
+            .set_hci_filter(filter.type_mask, filter.event_mask, filter.opcode)
+    }
+
+    /// Returns the filter selecting the packets received on this socket.
+    pub fn filter(&self) -> io::Result<HciFilter> {
+        let (type_mask, event_mask, opcode) = self.0.socket().hci_filter()?;
+        Ok(HciFilter {
+            type_mask,
+            event_mask,
+            opcode,
+        })
+    }
+
+    /// Sends a packet to the controller.
+    pub fn send(&self, packet: &HciPacket) -> io::Result<()> {
+        self.0.send(&packet.encode()?).map(|_| ())
+    }
+
+    /// Receives a single packet that passed the socket's filter.
+    ///
+    /// An error of kind `InvalidData` is returned for packets that cannot be decoded.
+    pub fn recv(&self) -> io::Result<HciPacket> {
+        let mut buf = [0; MAX_PACKET_SIZE];
+        let n = self.0.recv(&mut buf)?;
+        HciPacket::decode(&buf[..n])
+    }
+
+    /// Gets the value of the `SO_ERROR` option on this socket.
+    ///
+    /// This will retrieve the stored error in the underlying socket, clearing
+    /// the field in the process. This can be useful for checking errors between
+    /// calls.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.0.take_error()
+    }
+
+    /// Returns the read timeout of this socket.
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    /// Sets the read timeout to the timeout specified.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    /// Sets the write timeout to the timeout specified.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
+    /// Moves this socket into or out of nonblocking mode.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        self.0.duplicate().map(HciSocket)
+    }
+}
+
+impl fmt::Debug for HciSocket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl AsInner<bt_imp::HciSocket> for HciSocket {
+    fn as_inner(&self) -> &bt_imp::HciSocket {
+        &self.0
+    }
+}
+
+impl FromInner<bt_imp::HciSocket> for HciSocket {
+    fn from_inner(inner: bt_imp::HciSocket) -> HciSocket {
+        HciSocket(inner)
+    }
+}
+
+impl IntoInner<bt_imp::HciSocket> for HciSocket {
+    fn into_inner(self) -> bt_imp::HciSocket {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACKET_TYPES: [(HciPacketType, u32); 6] = [
+        (HciPacketType::Command, 1 << 1),
+        (HciPacketType::AclData, 1 << 2),
+        (HciPacketType::ScoData, 1 << 3),
+        (HciPacketType::Event, 1 << 4),
+        (HciPacketType::IsoData, 1 << 5),
+        (HciPacketType::Vendor, 1 << 31),
+    ];
+
+    #[test]
+    fn filter_packet_types() {
+        for &(ty, mask) in &PACKET_TYPES {
+            let mut filter = HciFilter::new();
+            assert!(!filter.has_packet_type(ty));
+
+            filter.set_packet_type(ty);
+            assert_eq!(filter.type_mask, mask, "{:?}", ty);
+            for &(other, _) in &PACKET_TYPES {
+                assert_eq!(filter.has_packet_type(other), other == ty);
+            }
+
+            filter.all_packet_types().clear_packet_type(ty);
+            assert_eq!(filter.type_mask, !mask);
+            assert!(!filter.has_packet_type(ty));
+        }
+    }
+
+    #[test]
+    fn filter_events() {
+        let cases = [
+            (0x00, [1, 0]),
+            (0x0e, [1 << 14, 0]),
+            (0x1f, [1 << 31, 0]),
+            (0x20, [0, 1]),
+            (0x3e, [0, 1 << 30]),
+            // Event codes wrap around at 64, as in the kernel.
+            (0x4e, [1 << 14, 0]),
+        ];
+        for &(event, mask) in &cases {
+            let mut filter = HciFilter::new();
+            filter.set_event(event);
+            assert_eq!(filter.event_mask, mask, "event {:#04x}", event);
+            assert!(filter.has_event(event));
+
+            filter.all_events().clear_event(event);
+            assert_eq!(filter.event_mask, [!mask[0], !mask[1]]);
+            assert!(!filter.has_event(event));
+        }
+    }
+
+    #[test]
+    fn packet_indicators() {
+        for &(ty, _) in &PACKET_TYPES {
+            assert_eq!(HciPacketType::from_indicator(u8::from(ty)), Some(ty));
+        }
+        assert_eq!(HciPacketType::from_indicator(0x00), None);
+        assert_eq!(HciPacketType::from_indicator(0x06), None);
+    }
+
+    #[test]
+    fn packet_encoding() {
+        let cases = vec![
+            // HCI_Reset
+            (
+                HciPacket::command(0x03, 0x0003, vec![]),
+                vec![0x01, 0x03, 0x0c, 0x00],
+            ),
+            // Command Complete for HCI_Reset
+            (
+                HciPacket::Event {
+                    code: 0x0e,
+                    params: vec![0x01, 0x03, 0x0c, 0x00],
+                },
+                vec![0x04, 0x0e, 0x04, 0x01, 0x03, 0x0c, 0x00],
+            ),
+            (
+                HciPacket::AclData {
+                    handle: 0x0042,
+                    flags: 0x2,
+                    data: vec![0xaa, 0xbb],
+                },
+                vec![0x02, 0x42, 0x20, 0x02, 0x00, 0xaa, 0xbb],
+            ),
+            (
+                HciPacket::Other {
+                    ty: HciPacketType::Vendor,
+                    payload: vec![0x12],
+                },
+                vec![0xff, 0x12],
+            ),
+        ];
+        for (packet, bytes) in cases {
+            assert_eq!(packet.encode().unwrap(), bytes);
+            assert_eq!(HciPacket::decode(&bytes).unwrap(), packet);
+        }
+
+        for bytes in &[
+            &[][..],
+            &[0x00],
+            &[0x01, 0x03, 0x0c],
+            &[0x04, 0x0e, 0x04, 0x01],
+        ] {
+            let err = HciPacket::decode(bytes).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
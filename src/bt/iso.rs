@@ -0,0 +1,405 @@
+use std::fmt;
+use std::io;
+use std::net::Shutdown;
+use std::time::{Duration, SystemTime};
+
+use super::{each_addr, BtAddr, BtAddrType, BtProtocol, BtSocketAddr};
+use crate::sys::bt::btc as c;
+use crate::sys::bt::Socket;
+use crate::sys_common::bt as bt_imp;
+use crate::sys_common::{AsInner, FromInner, IntoInner};
+
+/// The QoS parameters of one direction of an isochronous stream.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct BtIsoIoQos {
+    /// The SDU interval, in microseconds.
+    pub interval: u32,
+    /// The maximum transport latency, in milliseconds.
+    pub latency: u16,
+    /// The maximum SDU size, in bytes. A size of 0 disables this direction.
+    pub sdu: u16,
+    /// The PHYs that may be used, as a combination of the `PHY_*` constants.
+    pub phy: u8,
+    /// The number of retransmissions of each SDU.
+    pub rtn: u8,
+}
+
+impl BtIsoIoQos {
+    /// The LE 1M PHY.
+    pub const PHY_1M: u8 = 0x01;
+    /// The LE 2M PHY.
+    pub const PHY_2M: u8 = 0x02;
+    /// The LE Coded PHY.
+    pub const PHY_CODED: u8 = 0x04;
+}
+
+/// The QoS parameters of a connected (CIS) or broadcast (BIS) isochronous stream.
+///
+/// The input direction carries data from the controller to the host and the output
+/// direction carries data from the host to the controller.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct BtIsoQos {
+    /// The CIG or BIG identifier, or `None` to let the OS allocate one.
+    pub group: Option<u8>,
+    /// The CIS or BIS identifier, or `None` to let the OS allocate one.
+    pub stream: Option<u8>,
+    /// Whether SDUs are segmented into framed PDUs.
+    pub framed: bool,
+    /// The QoS parameters of received data.
+    pub input: BtIsoIoQos,
+    /// The QoS parameters of sent data.
+    pub output: BtIsoIoQos,
+    /// The code used to encrypt a broadcast isochronous group, or `None` for an
+    /// unencrypted one. Ignored for connected isochronous streams.
+    pub broadcast_code: Option<[u8; 16]>,
+}
+
+/// Information about an SDU received on a [`BtIsoStream`].
+///
+/// [`BtIsoStream`]: struct.BtIsoStream.html
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct BtIsoRecvInfo {
+    timestamp: Option<SystemTime>,
+    seqnum: Option<u16>,
+}
+
+impl BtIsoRecvInfo {
+    /// Returns the time at which the SDU was received, if timestamps are enabled with
+    /// [`set_timestamps`].
+    ///
+    /// [`set_timestamps`]: struct.BtIsoStream.html#method.set_timestamps
+    pub fn timestamp(&self) -> Option<SystemTime> {
+        self.timestamp
+    }
+
+    /// Returns the packet sequence number of the SDU, if sequence numbers are enabled
+    /// with [`set_pkt_seqnum`].
+    ///
+    /// [`set_pkt_seqnum`]: struct.BtIsoStream.html#method.set_pkt_seqnum
+    pub fn seqnum(&self) -> Option<u16> {
+        self.seqnum
+    }
+}
+
+/// An ISO socket server, listening for incoming connected isochronous streams or
+/// synchronizing to a broadcast isochronous group.
+///
+/// The socket will be closed when the value is dropped.
+pub struct BtIsoListener(bt_imp::BtListener);
+
+/// An LE isochronous stream, either connected (CIS) to a remote device or part of a
+/// broadcast isochronous group (BIS).
+///
+/// Each call to [`send`] transmits exactly one SDU and each call to [`recv`] receives
+/// exactly one SDU.
+///
+/// The stream will be closed when the value is dropped.
+///
+/// [`recv`]: #method.recv
+/// [`send`]: #method.send
+pub struct BtIsoStream(bt_imp::BtStream);
+
+impl BtIsoListener {
+    /// Creates a new `BtIsoListener` for connected isochronous streams, which will be
+    /// bound to the specified local LE address.
+    ///
+    /// If `addrs` yields multiple addresses, `bind` will be attempted with each of the
+    /// addresses until one succeeds and returns the socket. If none of the addresses
+    /// succeed in creating a socket, the error returned from the last attempt (the last
+    /// address) is returned.
+    pub fn bind<'a, I>(addrs: I, qos: &BtIsoQos) -> io::Result<Self>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        each_addr(addrs, |addr| {
+            bt_imp::BtListener::bind_with(addr, BtProtocol::ISO, c::SOCK_SEQPACKET, |s| {
+                s.set_iso_qos(qos, false)
+            })
+        })
+        .map(BtIsoListener)
+    }
+
+    /// Creates a new `BtIsoListener` which synchronizes to the broadcast isochronous
+    /// group advertised by `broadcaster` with the advertising set `sid`.
+    ///
+    /// `addr` is the local LE address to bind to and `bis` lists the indices of the
+    /// broadcast isochronous streams to receive, each in the range `1..=31`.
+    pub fn bind_broadcast(
+        addr: &BtSocketAddr,
+        broadcaster: &BtSocketAddr,
+        sid: u8,
+        bis: &[u8],
+        qos: &BtIsoQos,
+    ) -> io::Result<Self> {
+        bt_imp::BtListener::listen_with(BtProtocol::ISO, c::SOCK_SEQPACKET, |s| {
+            s.bind_iso_broadcast(addr, broadcaster, sid, bis)?;
+            s.set_iso_qos(qos, true)
+        })
+        .map(BtIsoListener)
+    }
+
+    /// Accept a new incoming isochronous stream from this listener.
+    ///
+    /// This function will block the calling thread until a new stream is established.
+    /// When established, the corresponding [`BtIsoStream`] and the remote peer's address
+    /// will be returned.
+    ///
+    /// [`BtIsoStream`]: struct.BtIsoStream.html
+    pub fn accept(&self) -> io::Result<(BtIsoStream, BtSocketAddr)> {
+        self.0.accept().map(|(a, b)| (BtIsoStream(a), b))
+    }
+
+    /// Returns the QoS parameters used for streams accepted by this listener.
+    pub fn qos(&self) -> io::Result<BtIsoQos> {
+        self.0.socket().iso_qos()
+    }
+
+    /// Returns the local socket address of this listener.
+    pub fn local_addr(&self) -> io::Result<BtSocketAddr> {
+        self.0.local_addr()
+    }
+
+    /// Get the value of the `SO_ERROR` option on this socket.
+    ///
+    /// This will retrieve the stored error in the underlying socket, clearing the field
+    /// in the process. This can be useful for checking errors between calls.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.0.take_error()
+    }
+
+    /// Moves this listener into or out of nonblocking mode.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        self.0.duplicate().map(BtIsoListener)
+    }
+}
+
+impl fmt::Debug for BtIsoListener {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl AsInner<bt_imp::BtListener> for BtIsoListener {
+    fn as_inner(&self) -> &bt_imp::BtListener {
+        &self.0
+    }
+}
+
+impl FromInner<bt_imp::BtListener> for BtIsoListener {
+    fn from_inner(inner: bt_imp::BtListener) -> BtIsoListener {
+        BtIsoListener(inner)
+    }
+}
+
+impl IntoInner<bt_imp::BtListener> for BtIsoListener {
+    fn into_inner(self) -> bt_imp::BtListener {
+        self.0
+    }
+}
+
+impl BtIsoStream {
+    /// Opens a connected isochronous stream to a remote LE device.
+    ///
+    /// If `addrs` yields multiple addresses, `connect` will be attempted with each of the
+    /// addresses until the underlying OS function returns no error. If the OS returns an
+    /// error for each of the specified addresses, the error returned from the last
+    /// connection attempt (the last address) is returned.
+    pub fn connect<'a, I>(addrs: I, qos: &BtIsoQos) -> io::Result<Self>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        each_addr(addrs, |addr| {
+            bt_imp::BtStream::connect_with(addr, BtProtocol::ISO, c::SOCK_SEQPACKET, |s| {
+                setup_connect(s, qos)
+            })
+        })
+        .map(BtIsoStream)
+    }
+
+    /// Opens a connected isochronous stream to a remote LE device with a timeout.
+    ///
+    /// It is an error to pass a zero `Duration` to this function.
+    pub fn connect_timeout(
+        addr: &BtSocketAddr,
+        qos: &BtIsoQos,
+        timeout: Duration,
+    ) -> io::Result<Self> {
+        bt_imp::BtStream::connect_timeout_with(
+            addr,
+            BtProtocol::ISO,
+            c::SOCK_SEQPACKET,
+            timeout,
+            |s| setup_connect(s, qos),
+        )
+        .map(BtIsoStream)
+    }
+
+    /// Creates a broadcast isochronous group from the local LE address `addr`, advertised
+    /// with the advertising set `sid`, and returns the stream to send on.
+    ///
+    /// The BIS index is taken from the `stream` of `qos`.
+    pub fn broadcast(addr: &BtSocketAddr, sid: u8, qos: &BtIsoQos) -> io::Result<Self> {
+        let any = any_le_addr();
+        let bis: Vec<u8> = qos.stream.into_iter().collect();
+        bt_imp::BtStream::connect_with(&any, BtProtocol::ISO, c::SOCK_SEQPACKET, |s| {
+            s.bind_iso_broadcast(addr, &any, sid, &bis)?;
+            s.set_iso_qos(qos, true)
+        })
+        .map(BtIsoStream)
+    }
+
+    /// Receives a single SDU. On success, returns the number of bytes read.
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.recv(buf)
+    }
+
+    /// Receives a single SDU, together with its timestamp and sequence number.
+    pub fn recv_with_info(&self, buf: &mut [u8]) -> io::Result<(usize, BtIsoRecvInfo)> {
+        let (n, timestamp, seqnum) = self.0.socket().recv_iso(buf)?;
+        Ok((n, BtIsoRecvInfo { timestamp, seqnum }))
+    }
+
+    /// Sends a single SDU. On success, returns the number of bytes written.
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.0.send(buf)
+    }
+
+    /// Returns the QoS parameters of this stream.
+    ///
+    /// The broadcast code is never reported.
+    pub fn qos(&self) -> io::Result<BtIsoQos> {
+        self.0.socket().iso_qos()
+    }
+
+    /// Enables or disables the reporting of receive timestamps (`SO_TIMESTAMPNS`) through
+    /// [`recv_with_info`].
+    ///
+    /// [`recv_with_info`]: #method.recv_with_info
+    pub fn set_timestamps(&self, enabled: bool) -> io::Result<()> {
+        self.0.socket().set_timestamps(enabled)
+    }
+
+    /// Returns whether the reporting of receive timestamps is enabled.
+    pub fn timestamps(&self) -> io::Result<bool> {
+        self.0.socket().timestamps()
+    }
+
+    /// Enables or disables the reporting of packet sequence numbers (`BT_PKT_SEQNUM`)
+    /// through [`recv_with_info`].
+    ///
+    /// [`recv_with_info`]: #method.recv_with_info
+    pub fn set_pkt_seqnum(&self, enabled: bool) -> io::Result<()> {
+        self.0.socket().set_pkt_seqnum(enabled)
+    }
+
+    /// Returns whether the reporting of packet sequence numbers is enabled.
+    pub fn pkt_seqnum(&self) -> io::Result<bool> {
+        self.0.socket().pkt_seqnum()
+    }
+
+    /// Shuts down the read, write, or both halves of this stream.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.0.shutdown(how)
+    }
+
+    /// Returns the socket address that this socket was created from.
+    pub fn local_addr(&self) -> io::Result<BtSocketAddr> {
+        self.0.local_addr()
+    }
+
+    /// Returns the socket address of the remote peer this socket was connected to.
+    pub fn peer_addr(&self) -> io::Result<BtSocketAddr> {
+        self.0.peer_addr()
+    }
+
+    /// Gets the value of the `SO_ERROR` option on this socket.
+    ///
+    /// This will retrieve the stored error in the underlying socket, clearing
+    /// the field in the process. This can be useful for checking errors between
+    /// calls.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.0.take_error()
+    }
+
+    /// Returns the read timeout of this socket.
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    /// Sets the read timeout to the timeout specified.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    /// Sets the write timeout to the timeout specified.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
+    /// Moves this socket into or out of nonblocking mode.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        self.0.duplicate().map(BtIsoStream)
+    }
+}
+
+impl fmt::Debug for BtIsoStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl AsInner<bt_imp::BtStream> for BtIsoStream {
+    fn as_inner(&self) -> &bt_imp::BtStream {
+        &self.0
+    }
+}
+
+impl FromInner<bt_imp::BtStream> for BtIsoStream {
+    fn from_inner(inner: bt_imp::BtStream) -> BtIsoStream {
+        BtIsoStream(inner)
+    }
+}
+
+impl IntoInner<bt_imp::BtStream> for BtIsoStream {
+    fn into_inner(self) -> bt_imp::BtStream {
+        self.0
+    }
+}
+
+fn any_le_addr() -> BtSocketAddr {
+    let mut addr = BtSocketAddr::new(BtAddr([0; 6]));
+    addr.set_addr_type(BtAddrType::LePublic);
+    addr
+}
+
+fn setup_connect(socket: &Socket, qos: &BtIsoQos) -> io::Result<()> {
+    // Like LE L2CAP sockets, ISO sockets need an LE source address type, which requires
+    // binding them before connecting.
+    bt_imp::bind(socket, &any_le_addr(), BtProtocol::ISO)?;
+    socket.set_iso_qos(qos, false)
+}
@@ -0,0 +1,200 @@
+use std::io;
+use std::net::Shutdown;
+use std::time::Duration;
+
+use super::{each_addr, BtAddr, BtListener, BtProtocol, BtSocketAddr, BtStream};
+use crate::sys::bt::btc as c;
+use crate::sys::bt::Socket;
+use crate::sys_common::bt as bt_imp;
+
+/// The mode of an L2CAP channel.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum L2capMode {
+    /// Basic mode, without flow control or retransmissions.
+    Basic,
+    /// Enhanced Retransmission Mode, which retransmits lost or corrupted PDUs.
+    Ertm,
+    /// Streaming mode, which drops lost or corrupted PDUs.
+    Streaming,
+    /// LE Credit Based Flow Control mode, as used by LE connection-oriented channels.
+    LeFlowControl,
+    /// Enhanced Credit Based Flow Control mode.
+    ExtFlowControl,
+}
+
+/// Options used to open L2CAP [`BtListener`]s and [`BtStream`]s with a specific MTU and
+/// channel mode.
+///
+/// The options are applied before connecting, and the values negotiated with the remote
+/// device are checked after connecting.
+///
+/// [`BtListener`]: struct.BtListener.html
+/// [`BtStream`]: struct.BtStream.html
+#[derive(Clone, Debug, Default)]
+pub struct BtL2capOptions {
+    recv_mtu: Option<u16>,
+    send_mtu: Option<u16>,
+    mode: Option<L2capMode>,
+}
+
+impl BtL2capOptions {
+    /// Creates a blank new set of options, using the OS defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the incoming MTU, i.e. the maximum size of an SDU that can be received.
+    pub fn recv_mtu(&mut self, mtu: u16) -> &mut Self {
+        self.recv_mtu = Some(mtu);
+        self
+    }
+
+    /// Sets the minimum outgoing MTU that the remote device must accept.
+    ///
+    /// The outgoing MTU is chosen by the remote device, so it is only checked after
+    /// connecting.
+    pub fn send_mtu(&mut self, mtu: u16) -> &mut Self {
+        self.send_mtu = Some(mtu);
+        self
+    }
+
+    /// Sets the channel mode.
+    pub fn mode(&mut self, mode: L2capMode) -> &mut Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Creates a new L2CAP [`BtListener`] using these options, which will be bound to the
+    /// specified address.
+    ///
+    /// If `addrs` yields multiple addresses, `bind` will be attempted with each of the
+    /// addresses until one succeeds and returns the socket. If none of the addresses
+    /// succeed in creating a socket, the error returned from the last attempt (the last
+    /// address) is returned.
+    ///
+    /// [`BtListener`]: struct.BtListener.html
+    pub fn bind<'a, I>(&self, addrs: I) -> io::Result<BtListener>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        each_addr(addrs, |addr| {
+            bt_imp::BtListener::bind_with(addr, BtProtocol::L2CAP, c::SOCK_STREAM, |s| {
+                self.apply(s)
+            })
+        })
+        .map(BtListener)
+    }
+
+    /// Opens an L2CAP connection to a remote device using these options.
+    ///
+    /// If the remote device does not accept the requested mode or outgoing MTU, the
+    /// connection is shut down and an error of kind `ConnectionAborted` is returned.
+    ///
+    /// If `addrs` yields multiple addresses, `connect` will be attempted with each of the
+    /// addresses until the underlying OS function returns no error. If the OS returns an
+    /// error for each of the specified addresses, the error returned from the last
+    /// connection attempt (the last address) is returned.
+    pub fn connect<'a, I>(&self, addrs: I) -> io::Result<BtStream>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        each_addr(addrs, |addr| {
+            let stream =
+                bt_imp::BtStream::connect_with(addr, BtProtocol::L2CAP, c::SOCK_STREAM, |s| {
+                    self.setup_connect(s)
+                })?;
+            self.check(&stream)?;
+            Ok(stream)
+        })
+        .map(BtStream)
+    }
+
+    /// Opens an L2CAP connection to a remote device with a timeout using these options.
+    ///
+    /// It is an error to pass a zero `Duration` to this function.
+    pub fn connect_timeout(&self, addr: &BtSocketAddr, timeout: Duration) -> io::Result<BtStream> {
+        let stream = bt_imp::BtStream::connect_timeout_with(
+            addr,
+            BtProtocol::L2CAP,
+            c::SOCK_STREAM,
+            timeout,
+            |s| self.setup_connect(s),
+        )?;
+        self.check(&stream)?;
+        Ok(BtStream(stream))
+    }
+
+    fn setup_connect(&self, socket: &Socket) -> io::Result<()> {
+        // With enhanced credit based flow control enabled, the kernel only accepts
+        // `BT_MODE` once the socket is bound.
+        bt_imp::bind(
+            socket,
+            &BtSocketAddr::new(BtAddr([0; 6])),
+            BtProtocol::L2CAP,
+        )?;
+        self.apply(socket)
+    }
+
+    fn apply(&self, socket: &Socket) -> io::Result<()> {
+        // The mode must be set first, as it determines which MTUs are valid.
+        if let Some(mode) = self.mode {
+            socket.set_l2cap_mode(mode)?;
+        }
+        if let Some(mtu) = self.recv_mtu {
+            socket.set_l2cap_recv_mtu(mtu)?;
+        }
+        Ok(())
+    }
+
+    fn check(&self, stream: &bt_imp::BtStream) -> io::Result<()> {
+        let socket = stream.socket();
+        let mode_ok = match self.mode {
+            Some(mode) => socket.l2cap_mode()? == mode,
+            None => true,
+        };
+        let mtu_ok = match self.send_mtu {
+            Some(mtu) => socket.l2cap_mtu()?.1 >= mtu,
+            None => true,
+        };
+
+        if mode_ok && mtu_ok {
+            Ok(())
+        } else {
+            let _ = stream.shutdown(Shutdown::Both);
+            Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "the remote device did not accept the requested L2CAP options",
+            ))
+        }
+    }
+}
+
+impl BtListener {
+    /// Returns the incoming MTU used for L2CAP connections accepted by this listener.
+    pub fn recv_mtu(&self) -> io::Result<u16> {
+        self.0.socket().l2cap_mtu().map(|(recv, _)| recv)
+    }
+
+    /// Returns the channel mode used for L2CAP connections accepted by this listener.
+    pub fn l2cap_mode(&self) -> io::Result<L2capMode> {
+        self.0.socket().l2cap_mode()
+    }
+}
+
+impl BtStream {
+    /// Returns the incoming MTU of this L2CAP connection.
+    pub fn recv_mtu(&self) -> io::Result<u16> {
+        self.0.socket().l2cap_mtu().map(|(recv, _)| recv)
+    }
+
+    /// Returns the outgoing MTU of this L2CAP connection, as negotiated with the remote
+    /// device.
+    pub fn send_mtu(&self) -> io::Result<u16> {
+        self.0.socket().l2cap_mtu().map(|(_, send)| send)
+    }
+
+    /// Returns the channel mode of this L2CAP connection.
+    pub fn l2cap_mode(&self) -> io::Result<L2capMode> {
+        self.0.socket().l2cap_mode()
+    }
+}
@@ -0,0 +1,126 @@
+use std::io;
+use std::ops::{BitOr, BitOrAssign};
+
+use super::{BtListener, BtStream};
+
+/// Link mode flags of an L2CAP or RFCOMM socket (`L2CAP_LM`/`RFCOMM_LM`).
+///
+/// Flags can be combined with `|`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct BtLinkMode(pub u32);
+
+impl BtLinkMode {
+    /// Request the central (master) role of the ACL link, switching roles on incoming
+    /// connections if necessary.
+    pub const CENTRAL: BtLinkMode = BtLinkMode(0x0001);
+    /// Require authentication.
+    pub const AUTH: BtLinkMode = BtLinkMode(0x0002);
+    /// Require encryption.
+    pub const ENCRYPT: BtLinkMode = BtLinkMode(0x0004);
+    /// Treat the remote device as trusted.
+    pub const TRUSTED: BtLinkMode = BtLinkMode(0x0008);
+    /// Close the connection if the ACL link is lost, rather than retrying.
+    pub const RELIABLE: BtLinkMode = BtLinkMode(0x0010);
+    /// Require a secure, authenticated link key.
+    pub const SECURE: BtLinkMode = BtLinkMode(0x0020);
+
+    /// Returns whether all the flags in `other` are set.
+    pub fn contains(self, other: BtLinkMode) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for BtLinkMode {
+    type Output = BtLinkMode;
+
+    fn bitor(self, rhs: BtLinkMode) -> BtLinkMode {
+        BtLinkMode(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for BtLinkMode {
+    fn bitor_assign(&mut self, rhs: BtLinkMode) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// The policy for moving an L2CAP channel between BR/EDR and AMP controllers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum BtChannelPolicy {
+    /// Only use BR/EDR. This is the default.
+    BrEdrOnly,
+    /// Allow AMP controllers, but prefer BR/EDR.
+    BrEdrPreferred,
+    /// Prefer AMP controllers when available.
+    AmpPreferred,
+}
+
+impl BtListener {
+    /// Sets the link mode flags for connections accepted by this L2CAP or RFCOMM listener.
+    pub fn set_link_mode(&self, mode: BtLinkMode) -> io::Result<()> {
+        self.0.socket().set_link_mode(self.0.protocol(), mode.0)
+    }
+
+    /// Returns the link mode flags for connections accepted by this listener.
+    pub fn link_mode(&self) -> io::Result<BtLinkMode> {
+        self.0.socket().link_mode(self.0.protocol()).map(BtLinkMode)
+    }
+
+    /// Sets whether the ACL link of accepted connections is kept in active mode, which
+    /// prevents it from entering sniff mode (`BT_POWER`).
+    pub fn set_force_active(&self, enabled: bool) -> io::Result<()> {
+        self.0.socket().set_force_active(enabled)
+    }
+
+    /// Returns whether the ACL link of accepted connections is kept in active mode.
+    pub fn force_active(&self) -> io::Result<bool> {
+        self.0.socket().force_active()
+    }
+}
+
+impl BtStream {
+    /// Sets the link mode flags of this L2CAP or RFCOMM connection.
+    pub fn set_link_mode(&self, mode: BtLinkMode) -> io::Result<()> {
+        self.0.socket().set_link_mode(self.0.protocol(), mode.0)
+    }
+
+    /// Returns the link mode flags of this connection.
+    pub fn link_mode(&self) -> io::Result<BtLinkMode> {
+        self.0.socket().link_mode(self.0.protocol()).map(BtLinkMode)
+    }
+
+    /// Sets whether the ACL link of this connection is kept in active mode, which prevents
+    /// it from entering sniff mode (`BT_POWER`).
+    pub fn set_force_active(&self, enabled: bool) -> io::Result<()> {
+        self.0.socket().set_force_active(enabled)
+    }
+
+    /// Returns whether the ACL link of this connection is kept in active mode.
+    pub fn force_active(&self) -> io::Result<bool> {
+        self.0.socket().force_active()
+    }
+
+    /// Sets whether data sent on this L2CAP connection may be flushed by the controller
+    /// when it cannot be delivered in time (`BT_FLUSHABLE`).
+    pub fn set_flushable(&self, enabled: bool) -> io::Result<()> {
+        self.0.socket().set_flushable(enabled)
+    }
+
+    /// Returns whether data sent on this L2CAP connection may be flushed.
+    pub fn flushable(&self) -> io::Result<bool> {
+        self.0.socket().flushable()
+    }
+
+    /// Sets the AMP channel policy of this L2CAP connection (`BT_CHANNEL_POLICY`).
+    ///
+    /// The policy can only be set on channels in ERTM or streaming mode, and only on
+    /// kernels with AMP support.
+    pub fn set_channel_policy(&self, policy: BtChannelPolicy) -> io::Result<()> {
+        self.0.socket().set_channel_policy(policy)
+    }
+
+    /// Returns the AMP channel policy of this L2CAP connection.
+    pub fn channel_policy(&self) -> io::Result<BtChannelPolicy> {
+        self.0.socket().channel_policy()
+    }
+}
@@ -0,0 +1,81 @@
+//! [mio] event source support for Bluetooth sockets.
+//!
+//! This module is only available with the `mio` feature enabled.
+//!
+//! [mio]: https://docs.rs/mio
+
+use std::io;
+use std::os::unix::io::AsRawFd;
+
+use ::mio::event::Source;
+use ::mio::unix::SourceFd;
+use ::mio::{Interest, Registry, Token};
+
+use super::{BtListener, BtProtocol, BtSocketAddr, BtStream};
+use crate::sys::bt::btc as c;
+use crate::sys_common::bt as bt_imp;
+
+impl BtStream {
+    /// Starts opening a Bluetooth connection to a remote host, without waiting for it to
+    /// be established.
+    ///
+    /// The returned stream is in nonblocking mode. Register it with a [`Registry`] for
+    /// [`Interest::WRITABLE`]; once it becomes writable, the connection attempt has
+    /// completed, and [`take_error`] reports whether it failed.
+    ///
+    /// [`Registry`]: https://docs.rs/mio/1/mio/struct.Registry.html
+    /// [`Interest::WRITABLE`]: https://docs.rs/mio/1/mio/struct.Interest.html#associatedconstant.WRITABLE
+    /// [`take_error`]: #method.take_error
+    pub fn connect_nonblocking(addr: &BtSocketAddr, protocol: BtProtocol) -> io::Result<Self> {
+        bt_imp::BtStream::connect_nonblocking(addr, protocol, c::SOCK_STREAM)
+            .map(|(stream, _)| BtStream(stream))
+    }
+}
+
+impl Source for BtStream {
+    fn register(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.as_raw_fd()).register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.as_raw_fd()).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+        SourceFd(&self.as_raw_fd()).deregister(registry)
+    }
+}
+
+impl Source for BtListener {
+    fn register(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.as_raw_fd()).register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.as_raw_fd()).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+        SourceFd(&self.as_raw_fd()).deregister(registry)
+    }
+}
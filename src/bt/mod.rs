@@ -3,6 +3,8 @@ use std::io;
 use std::net::Shutdown;
 use std::time::Duration;
 
+use crate::sys::bt as bt_sys;
+use crate::sys::bt::btc as c;
 use crate::sys_common::bt as bt_imp;
 use crate::sys_common::{AsInner, FromInner, IntoInner};
 
@@ -38,20 +40,273 @@ impl fmt::Display for BtAddr {
     }
 }
 
-#[derive(Clone, Copy)]
+/// The type of a Bluetooth device address.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum BtAddrType {
+    /// A BR/EDR (classic) device address.
+    #[default]
+    BrEdr,
+    /// A public Low Energy device address.
+    LePublic,
+    /// A random Low Energy device address.
+    LeRandom,
+}
+
+/// A Bluetooth socket address.
+///
+/// Besides the [`BtAddr`] of the device, a socket address identifies the endpoint on
+/// that device: the RFCOMM channel for RFCOMM sockets, or the PSM and CID for L2CAP
+/// sockets. A channel, PSM or CID of 0 is used when binding to let the OS pick one.
+///
+/// [`BtAddr`]: struct.BtAddr.html
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct BtSocketAddr {
+    addr: BtAddr,
+    addr_type: BtAddrType,
+    channel: u8,
+    psm: u16,
+    cid: u16,
+}
+
+impl BtSocketAddr {
+    /// Creates a new socket address for the specified device, without a channel, PSM or
+    /// CID.
+    pub fn new(addr: BtAddr) -> Self {
+        Self {
+            addr,
+            addr_type: BtAddrType::default(),
+            channel: 0,
+            psm: 0,
+            cid: 0,
+        }
+    }
+
+    /// Creates a new socket address for the specified RFCOMM channel of a device.
+    pub fn rfcomm(addr: BtAddr, channel: u8) -> Self {
+        Self {
+            channel,
+            ..Self::new(addr)
+        }
+    }
+
+    /// Creates a new socket address for the specified L2CAP PSM of a device.
+    ///
+    /// A valid PSM is odd and has the least significant bit of its most significant byte
+    /// cleared. PSMs below `0x1001` are assigned by the Bluetooth SIG and binding to them
+    /// usually requires elevated privileges; dynamic PSMs start at `0x1001`.
+    pub fn l2cap(addr: BtAddr, psm: u16) -> Self {
+        Self {
+            psm,
+            ..Self::new(addr)
+        }
+    }
+
+    /// Creates a new socket address for the specified LE L2CAP PSM of a device with the
+    /// given address type.
+    ///
+    /// LE PSMs below `0x0080` are assigned by the Bluetooth SIG; dynamic LE PSMs range
+    /// from `0x0080` to `0x00ff`.
+    pub fn l2cap_le(addr: BtAddr, addr_type: BtAddrType, psm: u16) -> Self {
+        Self {
+            addr_type,
+            psm,
+            ..Self::new(addr)
+        }
+    }
+
+    /// Returns the device address of this socket address.
+    pub fn addr(&self) -> &BtAddr {
+        &self.addr
+    }
+
+    /// Changes the device address of this socket address.
+    pub fn set_addr(&mut self, addr: BtAddr) {
+        self.addr = addr;
+    }
+
+    /// Returns the type of the device address.
+    pub fn addr_type(&self) -> BtAddrType {
+        self.addr_type
+    }
+
+    /// Changes the type of the device address.
+    pub fn set_addr_type(&mut self, addr_type: BtAddrType) {
+        self.addr_type = addr_type;
+    }
+
+    /// Returns the RFCOMM channel of this socket address.
+    pub fn channel(&self) -> u8 {
+        self.channel
+    }
+
+    /// Changes the RFCOMM channel of this socket address.
+    pub fn set_channel(&mut self, channel: u8) {
+        self.channel = channel;
+    }
+
+    /// Returns the L2CAP Protocol/Service Multiplexer (PSM) of this socket address.
+    pub fn psm(&self) -> u16 {
+        self.psm
+    }
+
+    /// Changes the L2CAP Protocol/Service Multiplexer (PSM) of this socket address.
+    pub fn set_psm(&mut self, psm: u16) {
+        self.psm = psm;
+    }
+
+    /// Returns the L2CAP Channel Identifier (CID) of this socket address.
+    pub fn cid(&self) -> u16 {
+        self.cid
+    }
+
+    /// Changes the L2CAP Channel Identifier (CID) of this socket address.
+    pub fn set_cid(&mut self, cid: u16) {
+        self.cid = cid;
+    }
+}
+
+impl From<BtAddr> for BtSocketAddr {
+    fn from(addr: BtAddr) -> Self {
+        Self::new(addr)
+    }
+}
+
+impl fmt::Display for BtSocketAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.addr)?;
+        if self.channel != 0 {
+            write!(f, " channel {}", self.channel)?;
+        }
+        if self.psm != 0 {
+            write!(f, " psm {:#06x}", self.psm)?;
+        }
+        if self.cid != 0 {
+            write!(f, " cid {:#06x}", self.cid)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum BtProtocol {
     L2CAP,
     RFCOMM,
+    /// Synchronous connection-oriented links, as used for audio.
+    SCO,
+    /// LE isochronous channels, as used for LE Audio.
+    ISO,
+}
+
+/// The security level required of a Bluetooth link.
+///
+/// Levels are ordered from the weakest to the strongest requirement.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum SecurityLevel {
+    /// No security at all. Only allowed for SDP connections.
+    Sdp,
+    /// No authentication or encryption is required. This is the default.
+    #[default]
+    Low,
+    /// Encryption is required, but the link key may be unauthenticated.
+    Medium,
+    /// Encryption with an authenticated link key is required, which protects against
+    /// man-in-the-middle attacks.
+    High,
+    /// Like `High`, with a link key generated using Secure Connections.
+    Fips,
+}
+
+/// The security of a Bluetooth link, as reported by the OS.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct SecurityInfo {
+    level: SecurityLevel,
+    key_size: u8,
+}
+
+impl SecurityInfo {
+    /// Returns the security level of the link.
+    ///
+    /// On listeners, and on streams whose link is not yet fully set up, this is the
+    /// required level rather than the negotiated one.
+    pub fn level(&self) -> SecurityLevel {
+        self.level
+    }
+
+    /// Returns the size of the encryption key in bytes, or 0 if the link is not
+    /// encrypted or the OS does not report it.
+    pub fn key_size(&self) -> u8 {
+        self.key_size
+    }
 }
 
-pub use crate::sys::bt::discover_devices;
+#[cfg(all(unix, feature = "async-io"))]
+pub use self::async_io::{AsyncBtListener, AsyncBtStream};
+#[cfg(unix)]
+pub use self::coc::BtLeCocOptions;
+#[cfg(unix)]
+pub use self::conninfo::BtConnectionInfo;
+pub use self::datagram::BtDatagram;
+#[cfg(unix)]
+pub use self::defer::BtPendingConnection;
+pub use self::discovery::{
+    discover_devices, discover_devices_with, DiscoveredDevice, DiscoveryOptions, InquiryAccessCode,
+    MajorDeviceClass,
+};
+#[cfg(unix)]
+pub use self::hci::{HciFilter, HciPacket, HciPacketType, HciSocket};
+#[cfg(unix)]
+pub use self::iso::{BtIsoIoQos, BtIsoListener, BtIsoQos, BtIsoRecvInfo, BtIsoStream};
+#[cfg(unix)]
+pub use self::l2cap::{BtL2capOptions, L2capMode};
+#[cfg(unix)]
+pub use self::link::{BtChannelPolicy, BtLinkMode};
+#[cfg(unix)]
+pub use self::msg::BtRecvInfo;
+pub use self::options::{BtListenerOptions, BtStreamOptions};
+pub use self::reconnect::{ConnectionState, LostWriteError, ReconnectOptions, ReconnectingStream};
+#[cfg(unix)]
+pub use self::sco::{BtScoListener, BtScoPacketStatus, BtScoStream, BtVoice};
+pub use self::seqpacket::{BtSeqPacket, BtSeqPacketListener};
+pub use self::split::{BtOwnedReadHalf, BtOwnedWriteHalf, BtReadHalf, BtReuniteError, BtWriteHalf};
+
+#[cfg(all(unix, feature = "async-io"))]
+mod async_io;
+#[cfg(unix)]
+mod coc;
+#[cfg(unix)]
+mod conninfo;
+mod datagram;
+#[cfg(unix)]
+mod defer;
+mod discovery;
+pub mod framing;
+#[cfg(unix)]
+mod hci;
+#[cfg(unix)]
+mod iso;
+#[cfg(unix)]
+mod l2cap;
+#[cfg(unix)]
+mod link;
+#[cfg(all(unix, feature = "mio"))]
+mod mio;
+#[cfg(unix)]
+mod msg;
+mod options;
+mod raw;
+mod reconnect;
+#[cfg(unix)]
+mod sco;
+mod seqpacket;
+mod split;
+#[cfg(all(unix, feature = "tokio"))]
+pub mod tokio;
 
 /// A Bluetooth socket server, listening for connections.
 ///
 /// After creating a `BtListener` by [`bind`]ing it to a Bluetooth address, it listens
 /// for incoming Bluetooth connections. These can be accepted by calling [`accept`] or by
-/// iterating over the [`Incoming`] iterator returned by [`incoming`]
-/// [`BtListener::incoming`].
+/// iterating over the [`Incoming`] iterator returned by [`incoming`].
 ///
 /// The socket will be closed when the value is dropped.
 ///
@@ -61,10 +316,22 @@ pub use crate::sys::bt::discover_devices;
 /// [`accept`]: #method.accept
 /// [`bind`]: #method.bind
 /// [Bluetooth Special Interest Group]: https://www.bluetooth.com/specifications
-/// [`Incoming`]: https://doc.rust-lang.org/std/net/struct.Incoming.html
-/// [`BtListener::incoming`]: #method.incoming
+/// [`Incoming`]: struct.Incoming.html
+/// [`incoming`]: #method.incoming
 pub struct BtListener(bt_imp::BtListener);
 
+/// An iterator that infinitely [`accept`]s connections on a [`BtListener`].
+///
+/// This `struct` is created by the [`incoming`] method on [`BtListener`].
+///
+/// [`accept`]: struct.BtListener.html#method.accept
+/// [`incoming`]: struct.BtListener.html#method.incoming
+/// [`BtListener`]: struct.BtListener.html
+#[derive(Debug)]
+pub struct Incoming<'a> {
+    listener: &'a BtListener,
+}
+
 /// A Bluetooth stream between a local and remote socket
 ///
 /// After creating a `BtStream` by either [`connect`]ing to a remote host or [`accept`]ing
@@ -92,20 +359,27 @@ impl BtListener {
     ///
     /// The returned listener is ready for accepting connections.
     ///
-    /// Binding with a port number of 0 will request that the OS assigns a port to this
-    /// listener. The port allocated can be queried via the [`local_addr`] method.
+    /// Binding with a channel or PSM of 0 will request that the OS assigns one to this
+    /// listener. The one allocated can be queried via the [`local_addr`] method.
     ///
     /// If `addrs` yields multiple addresses, `bind` will be attempted with each of the
     /// addresses until one succeeds and returns the socket. If none of the addresses
     /// succeed in creating a socket, the error returned from the last attempt (the last
     /// address) is returned.
     ///
+    /// The listener uses a backlog of 128 and sets `SO_REUSEADDR`; use
+    /// [`BtListenerOptions`] to configure these.
+    ///
     /// [`local_addr`]: #method.local_addr
+    /// [`BtListenerOptions`]: struct.BtListenerOptions.html
     pub fn bind<'a, I>(addrs: I, protocol: BtProtocol) -> io::Result<Self>
     where
-        I: Iterator<Item = &'a BtAddr>,
+        I: Iterator<Item = &'a BtSocketAddr>,
     {
-        each_addr(addrs, |addr| bt_imp::BtListener::bind(addr, protocol)).map(BtListener)
+        each_addr(addrs, |addr| {
+            bt_imp::BtListener::bind(addr, protocol, c::SOCK_STREAM)
+        })
+        .map(BtListener)
     }
 
     /// Accept a new incoming connection from this listener.
@@ -115,15 +389,31 @@ impl BtListener {
     /// peer's address will be returned.
     ///
     /// [`BtStream`]: bt/struct.BtStream.html
-    pub fn accept(&self) -> io::Result<(BtStream, BtAddr)> {
+    pub fn accept(&self) -> io::Result<(BtStream, BtSocketAddr)> {
         // On WASM, `TcpStream` is uninhabited (as it's unsupported) and so
         // the `a` variable here is technically unused.
         #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
         self.0.accept().map(|(a, b)| (BtStream(a), b))
     }
 
+    /// Returns an iterator over the connections being received on this listener.
+    ///
+    /// The returned iterator will never return [`None`] and will also not yield the
+    /// peer's [`BtSocketAddr`] structure. Iterating over it is equivalent to calling
+    /// [`accept`] in a loop.
+    ///
+    /// [`None`]: https://doc.rust-lang.org/std/option/enum.Option.html#variant.None
+    /// [`BtSocketAddr`]: struct.BtSocketAddr.html
+    /// [`accept`]: #method.accept
+    pub fn incoming(&self) -> Incoming<'_> {
+        Incoming { listener: self }
+    }
+
     /// Returns the local socket address of this listener.
-    pub fn local_addr(&self) -> io::Result<BtAddr> {
+    ///
+    /// If the listener was bound to channel or PSM 0, this returns the one that was
+    /// assigned by the OS.
+    pub fn local_addr(&self) -> io::Result<BtSocketAddr> {
         self.0.local_addr()
     }
 
@@ -132,6 +422,21 @@ impl BtListener {
         self.0.protocol()
     }
 
+    /// Sets the security level required of links accepted by this listener.
+    ///
+    /// Connections from devices which fail to meet the level are rejected by the OS and
+    /// never returned by [`accept`].
+    ///
+    /// [`accept`]: #method.accept
+    pub fn set_security(&self, level: SecurityLevel) -> io::Result<()> {
+        self.0.socket().set_security(level)
+    }
+
+    /// Returns the security level required of links accepted by this listener.
+    pub fn security(&self) -> io::Result<SecurityInfo> {
+        security(self.0.socket())
+    }
+
     /// Get the value of the `SO_ERROR` option on this socket.
     ///
     /// This will retrieve the stored error in the underlying socket, clearing the field
@@ -171,6 +476,14 @@ impl fmt::Debug for BtListener {
     }
 }
 
+impl<'a> Iterator for Incoming<'a> {
+    type Item = io::Result<BtStream>;
+
+    fn next(&mut self) -> Option<io::Result<BtStream>> {
+        Some(self.listener.accept().map(|p| p.0))
+    }
+}
+
 impl AsInner<bt_imp::BtListener> for BtListener {
     fn as_inner(&self) -> &bt_imp::BtListener {
         &self.0
@@ -198,17 +511,25 @@ impl BtStream {
     /// on the port, rather, such an error would only be detected after the first send. If
     /// the OS returns an error for each of the specified addresses, the error returned
     /// from the last connection attempt (the last address) is returned.
-    pub fn connect<'a, I: Iterator<Item = &'a BtAddr>>(
+    ///
+    /// The OS picks the local adapter to connect from; use [`BtStreamOptions`] to choose
+    /// one.
+    ///
+    /// [`BtStreamOptions`]: struct.BtStreamOptions.html
+    pub fn connect<'a, I: Iterator<Item = &'a BtSocketAddr>>(
         addrs: I,
         protocol: BtProtocol,
     ) -> io::Result<Self> {
-        each_addr(addrs, |addr| bt_imp::BtStream::connect(addr, protocol)).map(BtStream)
+        each_addr(addrs, |addr| {
+            bt_imp::BtStream::connect(addr, protocol, c::SOCK_STREAM)
+        })
+        .map(BtStream)
     }
 
     /// Opens a Bluetooth connection to a remote host with a timeout.
     ///
-    /// Unlike `connect`, `connect_timeout` takes a single [`BtAddr`] since timeout must
-    /// be applied to individual addresses.
+    /// Unlike `connect`, `connect_timeout` takes a single [`BtSocketAddr`] since timeout
+    /// must be applied to individual addresses.
     ///
     /// It is an error to pass a zero `Duration` to this function.
     ///
@@ -216,13 +537,44 @@ impl BtStream {
     /// call. It instead calls `connect` in nonblocking mode and then uses an OS-specific
     /// mechanism to await the completion of the connection request.
     ///
-    /// [`BtAddr`]: https://doc.rust-lang.org/std/net/enum.BtAddr.html
+    /// [`BtSocketAddr`]: struct.BtSocketAddr.html
     pub fn connect_timeout(
-        addr: &BtAddr,
+        addr: &BtSocketAddr,
         protocol: BtProtocol,
         timeout: Duration,
     ) -> io::Result<Self> {
-        bt_imp::BtStream::connect_timeout(addr, protocol, timeout).map(BtStream)
+        bt_imp::BtStream::connect_timeout(addr, protocol, c::SOCK_STREAM, timeout).map(BtStream)
+    }
+
+    /// Opens a Bluetooth connection to a remote host, requiring the link to meet the
+    /// security level `level`.
+    ///
+    /// Authentication or pairing failures are reported as errors of kind
+    /// `PermissionDenied`, which wrap the error of the OS, and a remote device rejecting
+    /// the connection for lack of security as `ConnectionRefused`. If the link is
+    /// established but its negotiated level is below `level`, the connection is shut down
+    /// and an error of kind `PermissionDenied` is returned.
+    ///
+    /// If `addrs` yields multiple addresses, `connect` will be attempted with each of the
+    /// addresses until the underlying OS function returns no error. If the OS returns an
+    /// error for each of the specified addresses, the error returned from the last
+    /// connection attempt (the last address) is returned.
+    pub fn connect_with_security<'a, I>(
+        addrs: I,
+        protocol: BtProtocol,
+        level: SecurityLevel,
+    ) -> io::Result<Self>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        BtStreamOptions::new(protocol)
+            .security(level)
+            .connect(addrs)
+    }
+
+    /// Returns the negotiated security level and encryption key size of this connection.
+    pub fn security(&self) -> io::Result<SecurityInfo> {
+        security(self.0.socket())
     }
 
     /// Receives single Bluetooth on the socket from the remote address to which it is
@@ -264,7 +616,8 @@ impl BtStream {
     ///
     /// Do not use this function to implement busy waiting, instead use `libc::poll` to
     /// synchronize IO events on one or more sockets.
-    pub fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, BtAddr)> {
+    #[deprecated(note = "stream sockets are connected; use `BtDatagram` instead")]
+    pub fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, BtSocketAddr)> {
         self.0.peek_from(buf)
     }
 
@@ -289,7 +642,8 @@ impl BtStream {
     /// The function must be called with valid byte array `buf` of sufficient size to hold
     /// the message bytes. If a message is too long to fit in the supplied buffer, excess
     /// bytes may be discarded.
-    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, BtAddr)> {
+    #[deprecated(note = "stream sockets are connected; use `BtDatagram` instead")]
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, BtSocketAddr)> {
         self.0.recv_from(buf)
     }
 
@@ -305,7 +659,8 @@ impl BtStream {
 
     /// Sends data on the socket to the given address. On success, returns the number of
     /// bytes written.
-    pub fn send_to(&self, buf: &[u8], dst: &BtAddr) -> io::Result<usize> {
+    #[deprecated(note = "stream sockets are connected; use `BtDatagram` instead")]
+    pub fn send_to(&self, buf: &[u8], dst: &BtSocketAddr) -> io::Result<usize> {
         self.0.send_to(buf, dst)
     }
 
@@ -327,12 +682,12 @@ impl BtStream {
     }
 
     /// Returns the socket address that this socket was created from.
-    pub fn local_addr(&self) -> io::Result<BtAddr> {
+    pub fn local_addr(&self) -> io::Result<BtSocketAddr> {
         self.0.local_addr()
     }
 
     /// Returns the socket address of the remote peer this socket was connected to.
-    pub fn peer_addr(&self) -> io::Result<BtAddr> {
+    pub fn peer_addr(&self) -> io::Result<BtSocketAddr> {
         self.0.peer_addr()
     }
 
@@ -452,6 +807,10 @@ impl io::Read for BtStream {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         self.0.recv(buf)
     }
+
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        self.0.recv_vectored(bufs)
+    }
 }
 
 impl io::Write for BtStream {
@@ -459,6 +818,34 @@ impl io::Write for BtStream {
         self.0.send(buf)
     }
 
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        self.0.send_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl io::Read for &BtStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.recv(buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        self.0.recv_vectored(bufs)
+    }
+}
+
+impl io::Write for &BtStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.send(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        self.0.send_vectored(bufs)
+    }
+
     fn flush(&mut self) -> io::Result<()> {
         Ok(())
     }
@@ -482,10 +869,19 @@ impl IntoInner<bt_imp::BtStream> for BtStream {
     }
 }
 
+fn security(socket: &bt_sys::Socket) -> io::Result<SecurityInfo> {
+    let (level, key_size) = socket.security()?;
+    Ok(SecurityInfo { level, key_size })
+}
+
+fn class_of_device(raw: [u8; 3]) -> u32 {
+    u32::from(raw[0]) | u32::from(raw[1]) << 8 | u32::from(raw[2]) << 16
+}
+
 fn each_addr<'a, I, F, T>(addrs: I, mut f: F) -> io::Result<T>
 where
-    F: FnMut(&'a BtAddr) -> io::Result<T>,
-    I: Iterator<Item = &'a BtAddr>,
+    F: FnMut(&'a BtSocketAddr) -> io::Result<T>,
+    I: Iterator<Item = &'a BtSocketAddr>,
 {
     let mut last_err = None;
     for addr in addrs {
@@ -503,4 +899,71 @@ where
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    const ADDR: BtAddr = BtAddr([0x66, 0x55, 0x44, 0x33, 0x22, 0x11]);
+
+    #[test]
+    fn addr_display() {
+        assert_eq!(ADDR.to_string(), "11:22:33:44:55:66");
+        assert_eq!(format!("{:?}", ADDR), "BtAddr(11:22:33:44:55:66)");
+        assert_eq!(
+            BtAddr::nap_sap(0x1122, 0x3344_5566).to_string(),
+            "11:22:33:44:55:66"
+        );
+    }
+
+    #[test]
+    fn socket_addr_display() {
+        let cases = [
+            (BtSocketAddr::new(ADDR), "11:22:33:44:55:66"),
+            (BtSocketAddr::rfcomm(ADDR, 3), "11:22:33:44:55:66 channel 3"),
+            (
+                BtSocketAddr::l2cap(ADDR, 0x1001),
+                "11:22:33:44:55:66 psm 0x1001",
+            ),
+            (
+                BtSocketAddr::l2cap_le(ADDR, BtAddrType::LeRandom, 0x0080),
+                "11:22:33:44:55:66 psm 0x0080",
+            ),
+        ];
+        for (addr, expected) in &cases {
+            assert_eq!(addr.to_string(), *expected);
+        }
+
+        let mut addr = BtSocketAddr::new(ADDR);
+        addr.set_cid(0x0004);
+        assert_eq!(addr.to_string(), "11:22:33:44:55:66 cid 0x0004");
+    }
+
+    #[test]
+    fn socket_addr_accessors() {
+        let addr = BtSocketAddr::rfcomm(ADDR, 3);
+        assert_eq!(*addr.addr(), ADDR);
+        assert_eq!(addr.addr_type(), BtAddrType::BrEdr);
+        assert_eq!((addr.channel(), addr.psm(), addr.cid()), (3, 0, 0));
+
+        let addr = BtSocketAddr::l2cap(ADDR, 0x1001);
+        assert_eq!(addr.addr_type(), BtAddrType::BrEdr);
+        assert_eq!((addr.channel(), addr.psm(), addr.cid()), (0, 0x1001, 0));
+
+        let addr = BtSocketAddr::l2cap_le(ADDR, BtAddrType::LePublic, 0x0080);
+        assert_eq!(addr.addr_type(), BtAddrType::LePublic);
+        assert_eq!((addr.channel(), addr.psm(), addr.cid()), (0, 0x0080, 0));
+
+        let mut addr = BtSocketAddr::from(BtAddr([0; 6]));
+        assert_eq!(addr, BtSocketAddr::new(BtAddr([0; 6])));
+        addr.set_addr(ADDR);
+        addr.set_addr_type(BtAddrType::LeRandom);
+        addr.set_channel(7);
+        addr.set_psm(0x0081);
+        addr.set_cid(0x0040);
+        assert_eq!(*addr.addr(), ADDR);
+        assert_eq!(addr.addr_type(), BtAddrType::LeRandom);
+        assert_eq!(
+            (addr.channel(), addr.psm(), addr.cid()),
+            (7, 0x0081, 0x0040)
+        );
+    }
+}
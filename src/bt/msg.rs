@@ -0,0 +1,133 @@
+use std::io::{self, IoSlice, IoSliceMut};
+use std::time::SystemTime;
+
+use super::{BtScoPacketStatus, BtScoStream, BtSeqPacket, BtStream};
+use crate::sys_common::bt as bt_imp;
+use crate::sys_common::AsInner;
+
+/// Ancillary data received along with a message by `recv_msg`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct BtRecvInfo {
+    timestamp: Option<SystemTime>,
+    pkt_status: Option<BtScoPacketStatus>,
+    truncated: bool,
+}
+
+impl BtRecvInfo {
+    /// Returns the time at which the message was received, if receive timestamps are
+    /// enabled (`SO_TIMESTAMP`, `SO_TIMESTAMPNS` or software or hardware receive
+    /// timestamps with `SO_TIMESTAMPING`).
+    pub fn timestamp(&self) -> Option<SystemTime> {
+        self.timestamp
+    }
+
+    /// Returns the packet status reported by the controller, if enabled with
+    /// [`BtScoStream::set_pkt_status`].
+    ///
+    /// [`BtScoStream::set_pkt_status`]: struct.BtScoStream.html#method.set_pkt_status
+    pub fn pkt_status(&self) -> Option<BtScoPacketStatus> {
+        self.pkt_status
+    }
+
+    /// Returns whether the message was truncated because it did not fit in the supplied
+    /// buffers.
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+fn recv_msg(
+    stream: &bt_imp::BtStream,
+    bufs: &mut [IoSliceMut<'_>],
+) -> io::Result<(usize, BtRecvInfo)> {
+    let (n, truncated, timestamp, pkt_status) = stream.socket().recv_msg(bufs)?;
+    let info = BtRecvInfo {
+        timestamp,
+        pkt_status: pkt_status.map(BtScoPacketStatus::from),
+        truncated,
+    };
+    Ok((n, info))
+}
+
+impl BtStream {
+    /// Receives data into `bufs`, together with the ancillary data received along with
+    /// it. On success, returns the number of bytes read.
+    pub fn recv_msg(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<(usize, BtRecvInfo)> {
+        recv_msg(self.as_inner(), bufs)
+    }
+
+    /// Sends the data gathered from `bufs`. On success, returns the number of bytes
+    /// written.
+    pub fn send_msg(&self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.as_inner().send_vectored(bufs)
+    }
+
+    /// Enables or disables the reporting of receive timestamps (`SO_TIMESTAMPNS`) through
+    /// [`recv_msg`].
+    ///
+    /// [`recv_msg`]: #method.recv_msg
+    pub fn set_timestamps(&self, enabled: bool) -> io::Result<()> {
+        self.as_inner().socket().set_timestamps(enabled)
+    }
+
+    /// Returns whether the reporting of receive timestamps is enabled.
+    pub fn timestamps(&self) -> io::Result<bool> {
+        self.as_inner().socket().timestamps()
+    }
+}
+
+impl BtSeqPacket {
+    /// Receives a single SDU into `bufs`, together with the ancillary data received
+    /// along with it. On success, returns the number of bytes read.
+    ///
+    /// The bytes of a truncated SDU that did not fit in `bufs` are discarded.
+    pub fn recv_msg(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<(usize, BtRecvInfo)> {
+        recv_msg(self.as_inner(), bufs)
+    }
+
+    /// Sends the data gathered from `bufs` as a single SDU. On success, returns the
+    /// number of bytes written.
+    pub fn send_msg(&self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.as_inner().send_vectored(bufs)
+    }
+
+    /// Enables or disables the reporting of receive timestamps (`SO_TIMESTAMPNS`) through
+    /// [`recv_msg`].
+    ///
+    /// [`recv_msg`]: #method.recv_msg
+    pub fn set_timestamps(&self, enabled: bool) -> io::Result<()> {
+        self.as_inner().socket().set_timestamps(enabled)
+    }
+
+    /// Returns whether the reporting of receive timestamps is enabled.
+    pub fn timestamps(&self) -> io::Result<bool> {
+        self.as_inner().socket().timestamps()
+    }
+}
+
+impl BtScoStream {
+    /// Receives a single audio frame into `bufs`, together with the ancillary data
+    /// received along with it. On success, returns the number of bytes read.
+    pub fn recv_msg(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<(usize, BtRecvInfo)> {
+        recv_msg(self.as_inner(), bufs)
+    }
+
+    /// Sends the data gathered from `bufs` as a single audio frame. On success, returns
+    /// the number of bytes written.
+    pub fn send_msg(&self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.as_inner().send_vectored(bufs)
+    }
+
+    /// Enables or disables the reporting of receive timestamps (`SO_TIMESTAMPNS`) through
+    /// [`recv_msg`].
+    ///
+    /// [`recv_msg`]: #method.recv_msg
+    pub fn set_timestamps(&self, enabled: bool) -> io::Result<()> {
+        self.as_inner().socket().set_timestamps(enabled)
+    }
+
+    /// Returns whether the reporting of receive timestamps is enabled.
+    pub fn timestamps(&self) -> io::Result<bool> {
+        self.as_inner().socket().timestamps()
+    }
+}
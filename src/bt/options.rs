@@ -0,0 +1,208 @@
+use std::convert::TryFrom;
+use std::io;
+use std::net::Shutdown;
+use std::os::raw::c_int;
+use std::time::Duration;
+
+use super::{each_addr, security, BtListener, BtProtocol, BtSocketAddr, BtStream, SecurityLevel};
+use crate::sys::bt as bt_sys;
+use crate::sys::bt::btc as c;
+use crate::sys::bt::Socket;
+use crate::sys_common::bt as bt_imp;
+
+/// Options and flags which can be used to configure how a [`BtListener`] is bound.
+///
+/// [`BtListener::bind`] is equivalent to binding with the default options, which use a
+/// backlog of 128 and set `SO_REUSEADDR`.
+///
+/// Binding to a channel (RFCOMM) or PSM (L2CAP) of 0 requests that the OS assigns a free
+/// one to the listener. The one allocated can be queried via
+/// [`BtListener::local_addr`].
+///
+/// [`BtListener`]: struct.BtListener.html
+/// [`BtListener::bind`]: struct.BtListener.html#method.bind
+/// [`BtListener::local_addr`]: struct.BtListener.html#method.local_addr
+#[derive(Clone, Debug)]
+pub struct BtListenerOptions {
+    protocol: BtProtocol,
+    backlog: u32,
+    reuse_addr: bool,
+    security: Option<SecurityLevel>,
+}
+
+impl BtListenerOptions {
+    /// Creates a new set of options for a listener of the given protocol.
+    pub fn new(protocol: BtProtocol) -> Self {
+        Self {
+            protocol,
+            backlog: 128,
+            reuse_addr: true,
+            security: None,
+        }
+    }
+
+    /// Sets the maximum number of pending connections that have not been accepted yet.
+    ///
+    /// The OS may silently cap the backlog to a lower value.
+    pub fn backlog(&mut self, backlog: u32) -> &mut Self {
+        self.backlog = backlog;
+        self
+    }
+
+    /// Sets whether `SO_REUSEADDR` is set on the socket, which allows a channel or PSM to
+    /// be rebound quickly after the previous listener using it was closed.
+    ///
+    /// This option has no effect on Windows.
+    pub fn reuse_addr(&mut self, reuse: bool) -> &mut Self {
+        self.reuse_addr = reuse;
+        self
+    }
+
+    /// Sets the security level required of links accepted by the listener.
+    ///
+    /// The level is set before the listener starts listening, so no connection is ever
+    /// accepted with a lower level.
+    pub fn security(&mut self, level: SecurityLevel) -> &mut Self {
+        self.security = Some(level);
+        self
+    }
+
+    /// Creates a new [`BtListener`] using these options, which will be bound to the
+    /// specified address.
+    ///
+    /// If `addrs` yields multiple addresses, `bind` will be attempted with each of the
+    /// addresses until one succeeds and returns the socket. If none of the addresses
+    /// succeed in creating a socket, the error returned from the last attempt (the last
+    /// address) is returned.
+    ///
+    /// [`BtListener`]: struct.BtListener.html
+    pub fn bind<'a, I>(&self, addrs: I) -> io::Result<BtListener>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        let backlog = c_int::try_from(self.backlog).unwrap_or(c_int::MAX);
+        each_addr(addrs, |addr| {
+            bt_imp::BtListener::listen_with_options(
+                self.protocol,
+                c::SOCK_STREAM,
+                backlog,
+                self.reuse_addr,
+                |socket| {
+                    bt_imp::bind(socket, addr, self.protocol)?;
+                    if let Some(level) = self.security {
+                        socket.set_security(level)?;
+                    }
+                    Ok(())
+                },
+            )
+        })
+        .map(BtListener)
+    }
+}
+
+/// Options and flags which can be used to configure a [`BtStream`] before it connects.
+///
+/// This allows choosing the local adapter that a connection is made from, on devices
+/// with multiple adapters, by binding the socket to the adapter's address. Without a
+/// local address, the OS picks the adapter.
+///
+/// [`BtStream`]: struct.BtStream.html
+#[derive(Clone, Debug)]
+pub struct BtStreamOptions {
+    protocol: BtProtocol,
+    local_addr: Option<BtSocketAddr>,
+    security: Option<SecurityLevel>,
+}
+
+impl BtStreamOptions {
+    /// Creates a new set of options for a connection of the given protocol.
+    pub fn new(protocol: BtProtocol) -> Self {
+        Self {
+            protocol,
+            local_addr: None,
+            security: None,
+        }
+    }
+
+    /// Sets the local address that the socket is bound to before connecting.
+    ///
+    /// The address should be that of a local adapter, with a channel or PSM of 0. The
+    /// address is reported by [`BtStream::local_addr`] once connected.
+    ///
+    /// [`BtStream::local_addr`]: struct.BtStream.html#method.local_addr
+    pub fn local_addr(&mut self, addr: BtSocketAddr) -> &mut Self {
+        self.local_addr = Some(addr);
+        self
+    }
+
+    /// Sets the security level required of the link.
+    ///
+    /// See [`BtStream::connect_with_security`] for how failures to meet the level are
+    /// reported.
+    ///
+    /// [`BtStream::connect_with_security`]: struct.BtStream.html#method.connect_with_security
+    pub fn security(&mut self, level: SecurityLevel) -> &mut Self {
+        self.security = Some(level);
+        self
+    }
+
+    /// Opens a connection to a remote host using these options.
+    ///
+    /// If `addrs` yields multiple addresses, `connect` will be attempted with each of the
+    /// addresses until the underlying OS function returns no error. If the OS returns an
+    /// error for each of the specified addresses, the error returned from the last
+    /// connection attempt (the last address) is returned.
+    pub fn connect<'a, I>(&self, addrs: I) -> io::Result<BtStream>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        each_addr(addrs, |addr| {
+            let stream = bt_imp::BtStream::connect_with(addr, self.protocol, c::SOCK_STREAM, |s| {
+                self.apply(s)
+            });
+            self.check(stream)
+        })
+        .map(BtStream)
+    }
+
+    /// Opens a connection to a remote host with a timeout using these options.
+    ///
+    /// It is an error to pass a zero `Duration` to this function.
+    pub fn connect_timeout(&self, addr: &BtSocketAddr, timeout: Duration) -> io::Result<BtStream> {
+        let stream = bt_imp::BtStream::connect_timeout_with(
+            addr,
+            self.protocol,
+            c::SOCK_STREAM,
+            timeout,
+            |s| self.apply(s),
+        );
+        self.check(stream).map(BtStream)
+    }
+
+    fn apply(&self, socket: &Socket) -> io::Result<()> {
+        if let Some(addr) = &self.local_addr {
+            bt_imp::bind(socket, addr, self.protocol)?;
+        }
+        if let Some(level) = self.security {
+            socket.set_security(level)?;
+        }
+        Ok(())
+    }
+
+    fn check(&self, stream: io::Result<bt_imp::BtStream>) -> io::Result<bt_imp::BtStream> {
+        let level = match self.security {
+            Some(level) => level,
+            None => return stream,
+        };
+        let stream = stream.map_err(bt_sys::security_error)?;
+
+        if security(stream.socket())?.level < level {
+            let _ = stream.shutdown(Shutdown::Both);
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "the link does not meet the required security level",
+            ));
+        }
+        Ok(stream)
+    }
+}
@@ -0,0 +1,140 @@
+//! Conversions between Bluetooth sockets and raw OS handles.
+//!
+//! Adopting an owned handle with `TryFrom` checks that it is a Bluetooth socket of a
+//! supported protocol. Adopting a raw handle assumes that it is one.
+
+use std::convert::TryFrom;
+use std::io;
+
+use super::{BtListener, BtStream};
+use crate::sys::bt::Socket;
+use crate::sys_common::bt as bt_imp;
+use crate::sys_common::{AsInner, FromInner, IntoInner};
+
+#[cfg(unix)]
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
+#[cfg(windows)]
+use std::os::windows::io::{
+    AsRawSocket, AsSocket, BorrowedSocket, FromRawSocket, IntoRawSocket, OwnedSocket, RawSocket,
+};
+
+macro_rules! adopt_raw {
+    ($t:ident, $owned:expr) => {
+        match $t::try_from($owned) {
+            Ok(socket) => socket,
+            Err(e) => panic!(
+                concat!("cannot adopt socket as a ", stringify!($t), ": {}"),
+                e
+            ),
+        }
+    };
+}
+
+macro_rules! impl_raw {
+    ($t:ident, $inner:path) => {
+        #[cfg(unix)]
+        impl AsRawFd for $t {
+            fn as_raw_fd(&self) -> RawFd {
+                *self.0.socket().as_inner()
+            }
+        }
+
+        #[cfg(unix)]
+        impl AsFd for $t {
+            fn as_fd(&self) -> BorrowedFd<'_> {
+                unsafe { BorrowedFd::borrow_raw(self.as_raw_fd()) }
+            }
+        }
+
+        #[cfg(unix)]
+        impl IntoRawFd for $t {
+            fn into_raw_fd(self) -> RawFd {
+                self.0.into_socket().into_inner()
+            }
+        }
+
+        /// # Safety
+        ///
+        /// `fd` must be an open Bluetooth socket of a supported protocol, owned by the
+        /// caller. Use `TryFrom<OwnedFd>` to adopt a file descriptor that may be of
+        /// another kind; passing one here panics.
+        #[cfg(unix)]
+        impl FromRawFd for $t {
+            unsafe fn from_raw_fd(fd: RawFd) -> $t {
+                adopt_raw!($t, OwnedFd::from_raw_fd(fd))
+            }
+        }
+
+        /// Fails if `fd` is not a Bluetooth socket of a supported protocol. The file
+        /// descriptor is closed in that case.
+        #[cfg(unix)]
+        impl TryFrom<OwnedFd> for $t {
+            type Error = io::Error;
+
+            fn try_from(fd: OwnedFd) -> io::Result<$t> {
+                <$inner>::from_socket(Socket::from_inner(fd.into_raw_fd())).map($t)
+            }
+        }
+
+        #[cfg(unix)]
+        impl From<$t> for OwnedFd {
+            fn from(socket: $t) -> OwnedFd {
+                unsafe { OwnedFd::from_raw_fd(socket.into_raw_fd()) }
+            }
+        }
+
+        #[cfg(windows)]
+        impl AsRawSocket for $t {
+            fn as_raw_socket(&self) -> RawSocket {
+                *self.0.socket().as_inner() as RawSocket
+            }
+        }
+
+        #[cfg(windows)]
+        impl AsSocket for $t {
+            fn as_socket(&self) -> BorrowedSocket<'_> {
+                unsafe { BorrowedSocket::borrow_raw(self.as_raw_socket()) }
+            }
+        }
+
+        #[cfg(windows)]
+        impl IntoRawSocket for $t {
+            fn into_raw_socket(self) -> RawSocket {
+                self.0.into_socket().into_inner() as RawSocket
+            }
+        }
+
+        /// # Safety
+        ///
+        /// `socket` must be an open Bluetooth socket of a supported protocol, owned by
+        /// the caller. Use `TryFrom<OwnedSocket>` to adopt a socket that may be of
+        /// another kind; passing one here panics.
+        #[cfg(windows)]
+        impl FromRawSocket for $t {
+            unsafe fn from_raw_socket(socket: RawSocket) -> $t {
+                adopt_raw!($t, OwnedSocket::from_raw_socket(socket))
+            }
+        }
+
+        /// Fails if `socket` is not a Bluetooth socket of a supported protocol. The
+        /// socket is closed in that case.
+        #[cfg(windows)]
+        impl TryFrom<OwnedSocket> for $t {
+            type Error = io::Error;
+
+            fn try_from(socket: OwnedSocket) -> io::Result<$t> {
+                <$inner>::from_socket(Socket::from_inner(socket.into_raw_socket() as _)).map($t)
+            }
+        }
+
+        #[cfg(windows)]
+        impl From<$t> for OwnedSocket {
+            fn from(socket: $t) -> OwnedSocket {
+                unsafe { OwnedSocket::from_raw_socket(socket.into_raw_socket()) }
+            }
+        }
+    };
+}
+
+impl_raw!(BtListener, bt_imp::BtListener);
+impl_raw!(BtStream, bt_imp::BtStream);
@@ -0,0 +1,479 @@
+use std::collections::hash_map::RandomState;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use super::{BtProtocol, BtSocketAddr, BtStream, BtStreamOptions};
+use crate::sys::bt as bt_sys;
+
+/// The state of the link of a [`ReconnectingStream`], as reported to the callback set
+/// with [`ReconnectOptions::on_state_change`].
+///
+/// [`ReconnectingStream`]: struct.ReconnectingStream.html
+/// [`ReconnectOptions::on_state_change`]: struct.ReconnectOptions.html#method.on_state_change
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ConnectionState {
+    /// A connection attempt is about to be made. Attempts are numbered from 1 for every
+    /// (re)connection.
+    Connecting { attempt: u32 },
+    /// The link is established.
+    Connected,
+    /// The link was lost.
+    Disconnected,
+    /// All connection attempts failed. The next read or write starts over.
+    Failed,
+}
+
+/// Options which can be used to configure how a [`ReconnectingStream`] (re-)establishes
+/// its link.
+///
+/// Failed connection attempts are retried after a delay that starts at the initial
+/// backoff, and is multiplied after each attempt up to the maximum backoff. Each delay is
+/// shortened by a random fraction of up to the jitter, so that devices which lost their
+/// links at the same time do not reconnect in lockstep.
+///
+/// [`ReconnectingStream`]: struct.ReconnectingStream.html
+#[derive(Clone)]
+pub struct ReconnectOptions {
+    stream: BtStreamOptions,
+    connect_timeout: Duration,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: f64,
+    max_attempts: Option<u32>,
+    on_state_change: Option<Arc<dyn Fn(ConnectionState) + Send + Sync>>,
+}
+
+/// A [`BtStream`] which transparently re-establishes its link when it is lost.
+///
+/// Reads that fail because the link was lost are retried once the stream has
+/// reconnected. Writes are not: data written to a lost link may not have reached the
+/// remote device, so the stream reconnects and then fails the write with an error of
+/// kind [`ConnectionReset`] wrapping a [`LostWriteError`]. It is up to the protocol
+/// spoken over the stream to decide what to resend.
+///
+/// [`BtStream`]: struct.BtStream.html
+/// [`ConnectionReset`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.ConnectionReset
+/// [`LostWriteError`]: struct.LostWriteError.html
+#[derive(Debug)]
+pub struct ReconnectingStream {
+    addr: BtSocketAddr,
+    options: ReconnectOptions,
+    stream: Option<BtStream>,
+}
+
+/// The error wrapped by the `io::Error` returned from a write to a [`ReconnectingStream`]
+/// whose link was lost.
+///
+/// None of the data passed to the failed write was sent. The stream tries to reconnect
+/// before the error is returned; if that fails as well, the reconnection error is
+/// available through [`reconnect_error`], and the next read or write starts over.
+///
+/// [`reconnect_error`]: #method.reconnect_error
+///
+/// [`ReconnectingStream`]: struct.ReconnectingStream.html
+#[derive(Debug)]
+pub struct LostWriteError {
+    unwritten: usize,
+    cause: io::Error,
+    reconnect_error: Option<io::Error>,
+}
+
+impl ReconnectOptions {
+    /// Creates a new set of options for a connection of the given protocol.
+    ///
+    /// By default, each connection attempt times out after 10 seconds, the backoff starts
+    /// at 500 milliseconds and doubles up to 30 seconds with a jitter of 20%, and the
+    /// stream keeps trying to reconnect forever.
+    pub fn new(protocol: BtProtocol) -> Self {
+        Self {
+            stream: BtStreamOptions::new(protocol),
+            connect_timeout: Duration::from_secs(10),
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_attempts: None,
+            on_state_change: None,
+        }
+    }
+
+    /// Sets the options used for each connection attempt, such as the local adapter or
+    /// security level.
+    pub fn stream_options(&mut self, options: BtStreamOptions) -> &mut Self {
+        self.stream = options;
+        self
+    }
+
+    /// Sets the timeout of each connection attempt.
+    ///
+    /// A zero `Duration` is rejected with an error of kind `InvalidInput` when
+    /// connecting.
+    pub fn connect_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Sets the delay before the first retry of a failed connection attempt.
+    pub fn initial_backoff(&mut self, backoff: Duration) -> &mut Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Sets the upper bound of the delay between connection attempts.
+    pub fn max_backoff(&mut self, backoff: Duration) -> &mut Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Sets the factor by which the delay grows after each failed connection attempt.
+    ///
+    /// Factors below 1 are treated as 1.
+    pub fn multiplier(&mut self, multiplier: f64) -> &mut Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Sets the largest fraction, between 0 and 1, by which each delay is randomly
+    /// shortened.
+    ///
+    /// Values outside of that range are clamped to it, and NaN is treated as 0.
+    pub fn jitter(&mut self, jitter: f64) -> &mut Self {
+        self.jitter = if jitter.is_nan() {
+            0.0
+        } else {
+            jitter.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    /// Sets the number of connection attempts after which (re)connecting fails, or `None`
+    /// to never give up.
+    pub fn max_attempts(&mut self, attempts: Option<u32>) -> &mut Self {
+        self.max_attempts = attempts;
+        self
+    }
+
+    /// Sets a callback which is called on every change of the link's state.
+    ///
+    /// The callback runs on the thread that is reading or writing, so it should not block.
+    /// To observe the state from another thread, send it through a channel.
+    pub fn on_state_change<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: Fn(ConnectionState) + Send + Sync + 'static,
+    {
+        self.on_state_change = Some(Arc::new(callback));
+        self
+    }
+
+    /// Opens a connection to a remote host using these options, retrying until it
+    /// succeeds or the maximum number of attempts is reached.
+    ///
+    /// Only failures that may go away, such as the remote device being out of range,
+    /// are retried. Any other error is returned right away.
+    pub fn connect(&self, addr: &BtSocketAddr) -> io::Result<ReconnectingStream> {
+        if self.connect_timeout == Duration::from_secs(0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot set a 0 duration timeout",
+            ));
+        }
+        let mut stream = ReconnectingStream {
+            addr: *addr,
+            options: self.clone(),
+            stream: None,
+        };
+        stream.reconnect()?;
+        Ok(stream)
+    }
+
+    fn notify(&self, state: ConnectionState) {
+        if let Some(callback) = &self.on_state_change {
+            callback(state);
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let max = self.max_backoff.as_secs_f64();
+        let delay =
+            (self.initial_backoff.as_secs_f64() * self.multiplier.max(1.0).powi(exponent)).min(max);
+
+        // A freshly keyed hasher is random enough for jitter, without depending on `rand`.
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        Duration::from_secs_f64(delay * (1.0 - self.jitter * random))
+    }
+}
+
+impl fmt::Debug for ReconnectOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReconnectOptions")
+            .field("stream", &self.stream)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("multiplier", &self.multiplier)
+            .field("jitter", &self.jitter)
+            .field("max_attempts", &self.max_attempts)
+            .field("on_state_change", &self.on_state_change.is_some())
+            .finish()
+    }
+}
+
+impl ReconnectingStream {
+    /// Opens a connection to a remote host with the default [`ReconnectOptions`].
+    ///
+    /// [`ReconnectOptions`]: struct.ReconnectOptions.html
+    pub fn connect(addr: &BtSocketAddr, protocol: BtProtocol) -> io::Result<Self> {
+        ReconnectOptions::new(protocol).connect(addr)
+    }
+
+    /// Returns the address of the remote device this stream connects to.
+    pub fn addr(&self) -> &BtSocketAddr {
+        &self.addr
+    }
+
+    /// Returns the current underlying stream, or `None` if the last attempt to reconnect
+    /// failed.
+    pub fn get_ref(&self) -> Option<&BtStream> {
+        self.stream.as_ref()
+    }
+
+    /// Consumes this `ReconnectingStream`, returning the current underlying stream.
+    pub fn into_inner(self) -> Option<BtStream> {
+        self.stream
+    }
+
+    /// Drops the current link, if any, and establishes a new one.
+    ///
+    /// If all connection attempts fail, the error of the last attempt is returned. Errors
+    /// that retrying cannot fix, such as an invalid address, are returned without
+    /// retrying.
+    pub fn reconnect(&mut self) -> io::Result<()> {
+        let options = &self.options;
+        if self.stream.take().is_some() {
+            options.notify(ConnectionState::Disconnected);
+        }
+
+        let mut attempt = 1;
+        loop {
+            options.notify(ConnectionState::Connecting { attempt });
+            match options
+                .stream
+                .connect_timeout(&self.addr, options.connect_timeout)
+            {
+                Ok(stream) => {
+                    self.stream = Some(stream);
+                    options.notify(ConnectionState::Connected);
+                    return Ok(());
+                }
+                Err(e)
+                    if !bt_sys::is_transient_connect_error(&e)
+                        || options.max_attempts.is_some_and(|max| attempt >= max) =>
+                {
+                    options.notify(ConnectionState::Failed);
+                    return Err(e);
+                }
+                Err(_) => {
+                    thread::sleep(options.backoff(attempt));
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    fn connected(&mut self) -> io::Result<&BtStream> {
+        if self.stream.is_none() {
+            self.reconnect()?;
+        }
+        Ok(self.stream.as_ref().unwrap())
+    }
+}
+
+impl io::Read for ReconnectingStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.connected()?.recv(buf) {
+                Err(ref e) if bt_sys::is_link_loss(e) => self.reconnect()?,
+                result => return result,
+            }
+        }
+    }
+}
+
+impl io::Write for ReconnectingStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.connected()?.send(buf) {
+            Err(e) if bt_sys::is_link_loss(&e) => {
+                let reconnect_error = self.reconnect().err();
+                Err(io::Error::new(
+                    io::ErrorKind::ConnectionReset,
+                    LostWriteError {
+                        unwritten: buf.len(),
+                        cause: e,
+                        reconnect_error,
+                    },
+                ))
+            }
+            result => result,
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl LostWriteError {
+    /// Returns the number of bytes that were not written.
+    pub fn unwritten(&self) -> usize {
+        self.unwritten
+    }
+
+    /// Returns the error that reported the loss of the link.
+    pub fn cause(&self) -> &io::Error {
+        &self.cause
+    }
+
+    /// Returns the error of the last attempt to reconnect, or `None` if the link was
+    /// reestablished.
+    pub fn reconnect_error(&self) -> Option<&io::Error> {
+        self.reconnect_error.as_ref()
+    }
+}
+
+impl fmt::Display for LostWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reconnect_error {
+            None => write!(
+                f,
+                "the link was lost and reestablished, {} bytes were not written: {}",
+                self.unwritten, self.cause
+            ),
+            Some(err) => write!(
+                f,
+                "the link was lost and could not be reestablished ({}), {} bytes were not \
+                 written: {}",
+                err, self.unwritten, self.cause
+            ),
+        }
+    }
+}
+
+impl Error for LostWriteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.cause)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bt::BtAddr;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn options(jitter: f64) -> ReconnectOptions {
+        let mut options = ReconnectOptions::new(BtProtocol::RFCOMM);
+        options
+            .initial_backoff(Duration::from_millis(500))
+            .max_backoff(Duration::from_secs(30))
+            .multiplier(2.0)
+            .jitter(jitter);
+        options
+    }
+
+    #[test]
+    fn backoff_grows_up_to_max() {
+        let options = options(0.0);
+        let delays: Vec<_> = (1..=9).map(|attempt| options.backoff(attempt)).collect();
+        let expected: Vec<_> = [500, 1000, 2000, 4000, 8000, 16000, 30000, 30000, 30000]
+            .iter()
+            .map(|&ms| Duration::from_millis(ms))
+            .collect();
+        assert_eq!(delays, expected);
+        assert_eq!(options.backoff(u32::MAX), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn backoff_jitter_bounds() {
+        let exact = options(0.0);
+        let jittered = options(0.2);
+        for attempt in 1..=8 {
+            let max = exact.backoff(attempt);
+            for _ in 0..100 {
+                let delay = jittered.backoff(attempt);
+                assert!(delay <= max, "{:?} > {:?}", delay, max);
+                assert!(delay >= max.mul_f64(0.8), "{:?} < 0.8 * {:?}", delay, max);
+            }
+        }
+    }
+
+    #[test]
+    fn backoff_sanitises_jitter() {
+        assert_eq!(options(f64::NAN).backoff(1), Duration::from_millis(500));
+        assert_eq!(options(-1.0).backoff(1), Duration::from_millis(500));
+        assert!(options(2.0).backoff(1) <= Duration::from_millis(500));
+    }
+
+    fn counting_attempts(options: &mut ReconnectOptions) -> Arc<AtomicU32> {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counter = attempts.clone();
+        options.on_state_change(move |state| {
+            if let ConnectionState::Connecting { .. } = state {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+        });
+        attempts
+    }
+
+    #[test]
+    fn zero_connect_timeout() {
+        let mut options = ReconnectOptions::new(BtProtocol::RFCOMM);
+        options.connect_timeout(Duration::from_secs(0));
+        let attempts = counting_attempts(&mut options);
+
+        let addr = BtSocketAddr::rfcomm(BtAddr([0; 6]), 1);
+        let err = options.connect(&addr).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(attempts.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn permanent_error_is_not_retried() {
+        let mut options = ReconnectOptions::new(BtProtocol::L2CAP);
+        options
+            .connect_timeout(Duration::from_secs(1))
+            .initial_backoff(Duration::from_millis(1))
+            .max_attempts(None);
+        let attempts = counting_attempts(&mut options);
+
+        // PSMs must be odd, so this fails whether or not the host has an adapter.
+        let addr = BtSocketAddr::l2cap(BtAddr([0; 6]), 0x0002);
+        let err = options.connect(&addr).unwrap_err();
+        assert!(!bt_sys::is_transient_connect_error(&err), "{:?}", err);
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn transient_connect_errors() {
+        for &kind in &[
+            io::ErrorKind::InvalidInput,
+            io::ErrorKind::AddrNotAvailable,
+            io::ErrorKind::PermissionDenied,
+        ] {
+            assert!(
+                !bt_sys::is_transient_connect_error(&kind.into()),
+                "{:?}",
+                kind
+            );
+        }
+        assert!(bt_sys::is_transient_connect_error(
+            &io::ErrorKind::TimedOut.into()
+        ));
+    }
+}
@@ -0,0 +1,329 @@
+use std::fmt;
+use std::io;
+use std::net::Shutdown;
+use std::time::Duration;
+
+use super::{each_addr, BtProtocol, BtSocketAddr};
+use crate::sys::bt::btc as c;
+use crate::sys_common::bt as bt_imp;
+use crate::sys_common::{AsInner, FromInner, IntoInner};
+
+/// The voice setting of a SCO link, as specified by the `Voice_Setting` HCI parameter.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct BtVoice(pub u16);
+
+impl BtVoice {
+    /// 16-bit linear PCM input, encoded as CVSD over the air.
+    pub const CVSD_16BIT: BtVoice = BtVoice(0x0060);
+    /// Transparent data, for codecs encoded by the host such as mSBC and LC3.
+    pub const TRANSPARENT: BtVoice = BtVoice(0x0003);
+}
+
+impl Default for BtVoice {
+    fn default() -> Self {
+        BtVoice::CVSD_16BIT
+    }
+}
+
+/// The status of a received SCO packet, as reported by the controller.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum BtScoPacketStatus {
+    /// The data was received correctly.
+    Correct,
+    /// The data is possibly invalid.
+    PossiblyInvalid,
+    /// No data was received; the packet is filled with zeroes.
+    NoData,
+    /// The data was partially lost.
+    PartiallyLost,
+}
+
+impl From<u8> for BtScoPacketStatus {
+    fn from(raw: u8) -> Self {
+        match raw & 0x03 {
+            0x00 => BtScoPacketStatus::Correct,
+            0x01 => BtScoPacketStatus::PossiblyInvalid,
+            0x02 => BtScoPacketStatus::NoData,
+            _ => BtScoPacketStatus::PartiallyLost,
+        }
+    }
+}
+
+/// A SCO socket server, listening for incoming synchronous audio links.
+///
+/// The socket will be closed when the value is dropped.
+pub struct BtScoListener(bt_imp::BtListener);
+
+/// A synchronous (SCO or eSCO) audio link between a local and remote device.
+///
+/// Each call to [`send`] transmits one audio frame and each call to [`recv`] receives
+/// one. Frames should not exceed the [`mtu`] of the link.
+///
+/// The connection will be closed when the value is dropped.
+///
+/// [`mtu`]: #method.mtu
+/// [`recv`]: #method.recv
+/// [`send`]: #method.send
+pub struct BtScoStream(bt_imp::BtStream);
+
+impl BtScoListener {
+    /// Creates a new `BtScoListener` which will be bound to the specified local adapter
+    /// address.
+    ///
+    /// Links accepted by the listener use the given voice setting, which must be set
+    /// before the listener starts listening.
+    ///
+    /// If `addrs` yields multiple addresses, `bind` will be attempted with each of the
+    /// addresses until one succeeds and returns the socket. If none of the addresses
+    /// succeed in creating a socket, the error returned from the last attempt (the last
+    /// address) is returned.
+    pub fn bind<'a, I>(addrs: I, voice: BtVoice) -> io::Result<Self>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        each_addr(addrs, |addr| {
+            bt_imp::BtListener::bind_with(addr, BtProtocol::SCO, c::SOCK_SEQPACKET, |s| {
+                s.set_voice(voice.0)
+            })
+        })
+        .map(BtScoListener)
+    }
+
+    /// Accept a new incoming audio link from this listener.
+    ///
+    /// This function will block the calling thread until a new link is established. When
+    /// established, the corresponding [`BtScoStream`] and the remote peer's address will
+    /// be returned.
+    ///
+    /// [`BtScoStream`]: struct.BtScoStream.html
+    pub fn accept(&self) -> io::Result<(BtScoStream, BtSocketAddr)> {
+        self.0.accept().map(|(a, b)| (BtScoStream(a), b))
+    }
+
+    /// Returns the voice setting used for links accepted by this listener.
+    pub fn voice(&self) -> io::Result<BtVoice> {
+        self.0.socket().voice().map(BtVoice)
+    }
+
+    /// Returns the local socket address of this listener.
+    pub fn local_addr(&self) -> io::Result<BtSocketAddr> {
+        self.0.local_addr()
+    }
+
+    /// Get the value of the `SO_ERROR` option on this socket.
+    ///
+    /// This will retrieve the stored error in the underlying socket, clearing the field
+    /// in the process. This can be useful for checking errors between calls.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.0.take_error()
+    }
+
+    /// Moves this listener into or out of nonblocking mode.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        self.0.duplicate().map(BtScoListener)
+    }
+}
+
+impl fmt::Debug for BtScoListener {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl AsInner<bt_imp::BtListener> for BtScoListener {
+    fn as_inner(&self) -> &bt_imp::BtListener {
+        &self.0
+    }
+}
+
+impl FromInner<bt_imp::BtListener> for BtScoListener {
+    fn from_inner(inner: bt_imp::BtListener) -> BtScoListener {
+        BtScoListener(inner)
+    }
+}
+
+impl IntoInner<bt_imp::BtListener> for BtScoListener {
+    fn into_inner(self) -> bt_imp::BtListener {
+        self.0
+    }
+}
+
+impl BtScoStream {
+    /// Opens an audio link to a remote device, using the specified voice setting.
+    ///
+    /// If `addrs` yields multiple addresses, `connect` will be attempted with each of the
+    /// addresses until the underlying OS function returns no error. If the OS returns an
+    /// error for each of the specified addresses, the error returned from the last
+    /// connection attempt (the last address) is returned.
+    pub fn connect<'a, I>(addrs: I, voice: BtVoice) -> io::Result<Self>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        each_addr(addrs, |addr| {
+            bt_imp::BtStream::connect_with(addr, BtProtocol::SCO, c::SOCK_SEQPACKET, |s| {
+                s.set_voice(voice.0)
+            })
+        })
+        .map(BtScoStream)
+    }
+
+    /// Opens an audio link to a remote device with a timeout, using the specified voice
+    /// setting.
+    ///
+    /// It is an error to pass a zero `Duration` to this function.
+    pub fn connect_timeout(
+        addr: &BtSocketAddr,
+        voice: BtVoice,
+        timeout: Duration,
+    ) -> io::Result<Self> {
+        bt_imp::BtStream::connect_timeout_with(
+            addr,
+            BtProtocol::SCO,
+            c::SOCK_SEQPACKET,
+            timeout,
+            |s| s.set_voice(voice.0),
+        )
+        .map(BtScoStream)
+    }
+
+    /// Receives a single audio frame. On success, returns the number of bytes read.
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.recv(buf)
+    }
+
+    /// Receives a single audio frame, together with its packet status.
+    ///
+    /// The packet status is only reported after enabling it with [`set_pkt_status`] and
+    /// when supported by the controller; otherwise `None` is returned for it.
+    ///
+    /// [`set_pkt_status`]: #method.set_pkt_status
+    pub fn recv_with_status(
+        &self,
+        buf: &mut [u8],
+    ) -> io::Result<(usize, Option<BtScoPacketStatus>)> {
+        let (n, status) = self.0.socket().recv_pkt_status(buf)?;
+        Ok((n, status.map(BtScoPacketStatus::from)))
+    }
+
+    /// Sends a single audio frame. On success, returns the number of bytes written.
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.0.send(buf)
+    }
+
+    /// Returns the voice setting of this link.
+    pub fn voice(&self) -> io::Result<BtVoice> {
+        self.0.socket().voice().map(BtVoice)
+    }
+
+    /// Returns the maximum size of an audio frame on this link, as reported by
+    /// `SCO_OPTIONS`.
+    pub fn mtu(&self) -> io::Result<u16> {
+        self.0.socket().sco_mtu()
+    }
+
+    /// Enables or disables the reporting of packet status (`BT_PKT_STATUS`) through
+    /// [`recv_with_status`].
+    ///
+    /// [`recv_with_status`]: #method.recv_with_status
+    pub fn set_pkt_status(&self, enabled: bool) -> io::Result<()> {
+        self.0.socket().set_pkt_status(enabled)
+    }
+
+    /// Returns whether the reporting of packet status is enabled.
+    pub fn pkt_status(&self) -> io::Result<bool> {
+        self.0.socket().pkt_status()
+    }
+
+    /// Shuts down the read, write, or both halves of this link.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.0.shutdown(how)
+    }
+
+    /// Returns the socket address that this socket was created from.
+    pub fn local_addr(&self) -> io::Result<BtSocketAddr> {
+        self.0.local_addr()
+    }
+
+    /// Returns the socket address of the remote peer this socket was connected to.
+    pub fn peer_addr(&self) -> io::Result<BtSocketAddr> {
+        self.0.peer_addr()
+    }
+
+    /// Gets the value of the `SO_ERROR` option on this socket.
+    ///
+    /// This will retrieve the stored error in the underlying socket, clearing
+    /// the field in the process. This can be useful for checking errors between
+    /// calls.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.0.take_error()
+    }
+
+    /// Returns the read timeout of this socket.
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    /// Sets the read timeout to the timeout specified.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    /// Sets the write timeout to the timeout specified.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
+    /// Moves this socket into or out of nonblocking mode.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        self.0.duplicate().map(BtScoStream)
+    }
+}
+
+impl fmt::Debug for BtScoStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl AsInner<bt_imp::BtStream> for BtScoStream {
+    fn as_inner(&self) -> &bt_imp::BtStream {
+        &self.0
+    }
+}
+
+impl FromInner<bt_imp::BtStream> for BtScoStream {
+    fn from_inner(inner: bt_imp::BtStream) -> BtScoStream {
+        BtScoStream(inner)
+    }
+}
+
+impl IntoInner<bt_imp::BtStream> for BtScoStream {
+    fn into_inner(self) -> bt_imp::BtStream {
+        self.0
+    }
+}
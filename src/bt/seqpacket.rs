@@ -0,0 +1,256 @@
+use std::fmt;
+use std::io;
+use std::net::Shutdown;
+use std::time::Duration;
+
+use super::{each_addr, BtProtocol, BtSocketAddr};
+use crate::sys::bt::btc as c;
+use crate::sys_common::bt as bt_imp;
+use crate::sys_common::{AsInner, FromInner, IntoInner};
+
+/// A sequenced packet L2CAP socket server, listening for connections.
+///
+/// Unlike a [`BtListener`], the connections accepted by a `BtSeqPacketListener` preserve
+/// the boundaries of L2CAP Service Data Units (SDUs), as relied upon by protocols such as
+/// HID and AVDTP.
+///
+/// The socket will be closed when the value is dropped.
+///
+/// [`BtListener`]: struct.BtListener.html
+pub struct BtSeqPacketListener(bt_imp::BtListener);
+
+/// A sequenced packet L2CAP connection between a local and remote socket.
+///
+/// Each call to [`send`] transmits exactly one L2CAP Service Data Unit (SDU) and each
+/// call to [`recv`] receives exactly one SDU.
+///
+/// The connection will be closed when the value is dropped.
+///
+/// [`recv`]: #method.recv
+/// [`send`]: #method.send
+pub struct BtSeqPacket(bt_imp::BtStream);
+
+impl BtSeqPacketListener {
+    /// Creates a new `BtSeqPacketListener` which will be bound to the specified address.
+    ///
+    /// The returned listener is ready for accepting connections.
+    ///
+    /// Binding with a PSM of 0 will request that the OS assigns one to this listener. The
+    /// PSM allocated can be queried via the [`local_addr`] method.
+    ///
+    /// If `addrs` yields multiple addresses, `bind` will be attempted with each of the
+    /// addresses until one succeeds and returns the socket. If none of the addresses
+    /// succeed in creating a socket, the error returned from the last attempt (the last
+    /// address) is returned.
+    ///
+    /// [`local_addr`]: #method.local_addr
+    pub fn bind<'a, I>(addrs: I) -> io::Result<Self>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        each_addr(addrs, |addr| {
+            bt_imp::BtListener::bind(addr, BtProtocol::L2CAP, c::SOCK_SEQPACKET)
+        })
+        .map(BtSeqPacketListener)
+    }
+
+    /// Accept a new incoming connection from this listener.
+    ///
+    /// This function will block the calling thread until a new connection is
+    /// established. When established, the corresponding [`BtSeqPacket`] and the remote
+    /// peer's address will be returned.
+    ///
+    /// [`BtSeqPacket`]: struct.BtSeqPacket.html
+    pub fn accept(&self) -> io::Result<(BtSeqPacket, BtSocketAddr)> {
+        self.0.accept().map(|(a, b)| (BtSeqPacket(a), b))
+    }
+
+    /// Returns the local socket address of this listener.
+    pub fn local_addr(&self) -> io::Result<BtSocketAddr> {
+        self.0.local_addr()
+    }
+
+    /// Get the value of the `SO_ERROR` option on this socket.
+    ///
+    /// This will retrieve the stored error in the underlying socket, clearing the field
+    /// in the process. This can be useful for checking errors between calls.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.0.take_error()
+    }
+
+    /// Moves this listener into or out of nonblocking mode.
+    ///
+    /// This will result in the `accept` operation becoming nonblocking, i.e., immediately
+    /// returning from their calls. If the IO operation is successful, `Ok` is returned
+    /// and no further action is required. If the IO operation could not be completed and
+    /// needs to be retried, an error with kind [`io::ErrorKind::WouldBlock`] is returned.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        self.0.duplicate().map(BtSeqPacketListener)
+    }
+}
+
+impl fmt::Debug for BtSeqPacketListener {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl AsInner<bt_imp::BtListener> for BtSeqPacketListener {
+    fn as_inner(&self) -> &bt_imp::BtListener {
+        &self.0
+    }
+}
+
+impl FromInner<bt_imp::BtListener> for BtSeqPacketListener {
+    fn from_inner(inner: bt_imp::BtListener) -> BtSeqPacketListener {
+        BtSeqPacketListener(inner)
+    }
+}
+
+impl IntoInner<bt_imp::BtListener> for BtSeqPacketListener {
+    fn into_inner(self) -> bt_imp::BtListener {
+        self.0
+    }
+}
+
+impl BtSeqPacket {
+    /// Opens a sequenced packet L2CAP connection to a remote host.
+    ///
+    /// If `addrs` yields multiple addresses, `connect` will be attempted with each of the
+    /// addresses until the underlying OS function returns no error. If the OS returns an
+    /// error for each of the specified addresses, the error returned from the last
+    /// connection attempt (the last address) is returned.
+    pub fn connect<'a, I: Iterator<Item = &'a BtSocketAddr>>(addrs: I) -> io::Result<Self> {
+        each_addr(addrs, |addr| {
+            bt_imp::BtStream::connect(addr, BtProtocol::L2CAP, c::SOCK_SEQPACKET)
+        })
+        .map(BtSeqPacket)
+    }
+
+    /// Opens a sequenced packet L2CAP connection to a remote host with a timeout.
+    ///
+    /// It is an error to pass a zero `Duration` to this function.
+    pub fn connect_timeout(addr: &BtSocketAddr, timeout: Duration) -> io::Result<Self> {
+        bt_imp::BtStream::connect_timeout(addr, BtProtocol::L2CAP, c::SOCK_SEQPACKET, timeout)
+            .map(BtSeqPacket)
+    }
+
+    /// Receives a single SDU from the remote address to which the socket is connected,
+    /// without removing it from the input queue.
+    ///
+    /// On success, returns the number of bytes peeked and whether the SDU was truncated
+    /// because it did not fit in `buf`.
+    pub fn peek(&self, buf: &mut [u8]) -> io::Result<(usize, bool)> {
+        self.0.peek_trunc(buf)
+    }
+
+    /// Receives a single SDU from the remote address to which the socket is connected.
+    ///
+    /// On success, returns the number of bytes read and whether the SDU was truncated
+    /// because it did not fit in `buf`. The bytes of a truncated SDU that did not fit in
+    /// `buf` are discarded. A buffer the size of the incoming MTU is always sufficient.
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<(usize, bool)> {
+        self.0.recv_trunc(buf)
+    }
+
+    /// Sends `buf` as a single SDU to the remote address to which the socket is
+    /// connected.
+    ///
+    /// An SDU larger than the outgoing MTU is rejected by the OS.
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.0.send(buf)
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.0.shutdown(how)
+    }
+
+    /// Returns the socket address that this socket was created from.
+    pub fn local_addr(&self) -> io::Result<BtSocketAddr> {
+        self.0.local_addr()
+    }
+
+    /// Returns the socket address of the remote peer this socket was connected to.
+    pub fn peer_addr(&self) -> io::Result<BtSocketAddr> {
+        self.0.peer_addr()
+    }
+
+    /// Gets the value of the `SO_ERROR` option on this socket.
+    ///
+    /// This will retrieve the stored error in the underlying socket, clearing
+    /// the field in the process. This can be useful for checking errors between
+    /// calls.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.0.take_error()
+    }
+
+    /// Returns the read timeout of this socket.
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    /// Sets the read timeout to the timeout specified.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    /// Sets the write timeout to the timeout specified.
+    ///
+    /// An [`Err`] is returned if the zero [`Duration`] is passed to this method.
+    ///
+    /// [`Err`]: https://doc.rust-lang.org/std/result/enum.Result.html#variant.Err
+    /// [`Duration`]: https://doc.rust-lang.org/std/time/struct.Duration.html
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
+    /// Moves this socket into or out of nonblocking mode.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.0.set_nonblocking(nonblocking)
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        self.0.duplicate().map(BtSeqPacket)
+    }
+}
+
+impl fmt::Debug for BtSeqPacket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl AsInner<bt_imp::BtStream> for BtSeqPacket {
+    fn as_inner(&self) -> &bt_imp::BtStream {
+        &self.0
+    }
+}
+
+impl FromInner<bt_imp::BtStream> for BtSeqPacket {
+    fn from_inner(inner: bt_imp::BtStream) -> BtSeqPacket {
+        BtSeqPacket(inner)
+    }
+}
+
+impl IntoInner<bt_imp::BtStream> for BtSeqPacket {
+    fn into_inner(self) -> bt_imp::BtStream {
+        self.0
+    }
+}
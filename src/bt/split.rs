@@ -0,0 +1,394 @@
+//! Read and write halves of Bluetooth streams, for using one from two tasks or threads
+//! at once.
+//!
+//! The halves are shared by the blocking [`BtStream`] and its asynchronous counterparts,
+//! and implement the same I/O traits as a shared reference to the stream they split.
+//!
+//! [`BtStream`]: struct.BtStream.html
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::sync::Arc;
+
+#[cfg(any(all(unix, feature = "async-io"), all(unix, feature = "tokio")))]
+use std::pin::Pin;
+#[cfg(any(all(unix, feature = "async-io"), all(unix, feature = "tokio")))]
+use std::task::{Context, Poll};
+
+use super::BtStream;
+
+/// The read half of a stream, borrowed from it by `split`.
+#[derive(Debug)]
+pub struct BtReadHalf<'a, S = BtStream>(&'a S);
+
+/// The write half of a stream, borrowed from it by `split`.
+#[derive(Debug)]
+pub struct BtWriteHalf<'a, S = BtStream>(&'a S);
+
+/// The owned read half of a stream, created by `into_split`.
+///
+/// The halves can be moved to different tasks or threads, and joined back together with
+/// [`reunite`].
+///
+/// [`reunite`]: #method.reunite
+#[derive(Debug)]
+pub struct BtOwnedReadHalf<S = BtStream> {
+    inner: Arc<S>,
+}
+
+/// The owned write half of a stream, created by `into_split`.
+///
+/// Dropping the write half does not shut down the stream; use [`shutdown`] to signal the
+/// end of the data to the remote peer.
+///
+/// [`shutdown`]: #method.shutdown
+#[derive(Debug)]
+pub struct BtOwnedWriteHalf<S = BtStream> {
+    inner: Arc<S>,
+}
+
+/// An error returned when reuniting two halves that were not split from the same stream.
+///
+/// The halves are handed back so they are not lost.
+#[derive(Debug)]
+pub struct BtReuniteError<S = BtStream>(pub BtOwnedReadHalf<S>, pub BtOwnedWriteHalf<S>);
+
+pub fn split<S>(stream: &S) -> (BtReadHalf<'_, S>, BtWriteHalf<'_, S>) {
+    (BtReadHalf(stream), BtWriteHalf(stream))
+}
+
+pub fn into_split<S>(stream: S) -> (BtOwnedReadHalf<S>, BtOwnedWriteHalf<S>) {
+    let inner = Arc::new(stream);
+    (
+        BtOwnedReadHalf {
+            inner: Arc::clone(&inner),
+        },
+        BtOwnedWriteHalf { inner },
+    )
+}
+
+impl BtStream {
+    /// Splits this stream into a read half and a write half, which can be used
+    /// concurrently without duplicating the underlying socket.
+    pub fn split(&self) -> (BtReadHalf<'_>, BtWriteHalf<'_>) {
+        split(self)
+    }
+
+    /// Splits this stream into owned read and write halves, which can be moved to
+    /// different threads without duplicating the underlying socket.
+    ///
+    /// The halves can be joined back into a `BtStream` with [`BtOwnedReadHalf::reunite`].
+    ///
+    /// [`BtOwnedReadHalf::reunite`]: struct.BtOwnedReadHalf.html#method.reunite
+    pub fn into_split(self) -> (BtOwnedReadHalf, BtOwnedWriteHalf) {
+        into_split(self)
+    }
+}
+
+impl<'a, S> AsRef<S> for BtReadHalf<'a, S> {
+    fn as_ref(&self) -> &S {
+        self.0
+    }
+}
+
+impl<'a, S> AsRef<S> for BtWriteHalf<'a, S> {
+    fn as_ref(&self) -> &S {
+        self.0
+    }
+}
+
+impl<S> BtOwnedReadHalf<S> {
+    /// Attempts to put the two halves of a stream back together.
+    ///
+    /// This fails if the halves did not come from the same `into_split` call.
+    pub fn reunite(self, other: BtOwnedWriteHalf<S>) -> Result<S, BtReuniteError<S>> {
+        reunite(self, other)
+    }
+}
+
+impl<S> BtOwnedWriteHalf<S> {
+    /// Attempts to put the two halves of a stream back together.
+    ///
+    /// This fails if the halves did not come from the same `into_split` call.
+    pub fn reunite(self, other: BtOwnedReadHalf<S>) -> Result<S, BtReuniteError<S>> {
+        reunite(other, self)
+    }
+}
+
+impl BtOwnedWriteHalf {
+    /// Shuts down the write half of the stream.
+    ///
+    /// The read half is unaffected, so the remote peer's remaining data can still be
+    /// received.
+    pub fn shutdown(&self) -> io::Result<()> {
+        self.inner.shutdown(Shutdown::Write)
+    }
+}
+
+fn reunite<S>(
+    read: BtOwnedReadHalf<S>,
+    write: BtOwnedWriteHalf<S>,
+) -> Result<S, BtReuniteError<S>> {
+    if !Arc::ptr_eq(&read.inner, &write.inner) {
+        return Err(BtReuniteError(read, write));
+    }
+    drop(write);
+    match Arc::try_unwrap(read.inner) {
+        Ok(stream) => Ok(stream),
+        Err(_) => unreachable!("the stream is only shared between its two halves"),
+    }
+}
+
+impl<S> AsRef<S> for BtOwnedReadHalf<S> {
+    fn as_ref(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S> AsRef<S> for BtOwnedWriteHalf<S> {
+    fn as_ref(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S> fmt::Display for BtReuniteError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("tried to reunite halves that are not from the same stream")
+    }
+}
+
+impl<S: fmt::Debug> Error for BtReuniteError<S> {}
+
+impl<'a, S> Read for BtReadHalf<'a, S>
+where
+    &'a S: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        self.0.read_vectored(bufs)
+    }
+}
+
+impl<'a, S> Write for BtWriteHalf<'a, S>
+where
+    &'a S: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        self.0.write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl<S> Read for BtOwnedReadHalf<S>
+where
+    for<'a> &'a S: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self.inner).read(buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        (&*self.inner).read_vectored(bufs)
+    }
+}
+
+impl<S> Write for BtOwnedWriteHalf<S>
+where
+    for<'a> &'a S: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self.inner).write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        (&*self.inner).write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self.inner).flush()
+    }
+}
+
+#[cfg(all(unix, feature = "async-io"))]
+mod futures_impls {
+    use super::*;
+    use futures_io::{AsyncRead, AsyncWrite};
+
+    impl<'a, S> AsyncRead for BtReadHalf<'a, S>
+    where
+        &'a S: AsyncRead,
+    {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.0).poll_read(cx, buf)
+        }
+    }
+
+    impl<'a, S> AsyncWrite for BtWriteHalf<'a, S>
+    where
+        &'a S: AsyncWrite,
+    {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.0).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.0).poll_flush(cx)
+        }
+
+        fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.0).poll_close(cx)
+        }
+    }
+
+    impl<S> AsyncRead for BtOwnedReadHalf<S>
+    where
+        for<'a> &'a S: AsyncRead,
+    {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut &*self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl<S> AsyncWrite for BtOwnedWriteHalf<S>
+    where
+        for<'a> &'a S: AsyncWrite,
+    {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut &*self.inner).poll_write(cx, buf)
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut &*self.inner).poll_flush(cx)
+        }
+
+        fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut &*self.inner).poll_close(cx)
+        }
+    }
+}
+
+#[cfg(all(unix, feature = "tokio"))]
+mod tokio_impls {
+    use super::*;
+    use ::tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+    impl<'a, S> AsyncRead for BtReadHalf<'a, S>
+    where
+        &'a S: AsyncRead,
+    {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.0).poll_read(cx, buf)
+        }
+    }
+
+    impl<'a, S> AsyncWrite for BtWriteHalf<'a, S>
+    where
+        &'a S: AsyncWrite,
+    {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.0).poll_write(cx, buf)
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[io::IoSlice<'_>],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.0).poll_write_vectored(cx, bufs)
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            self.0.is_write_vectored()
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.0).poll_flush(cx)
+        }
+
+        fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.0).poll_shutdown(cx)
+        }
+    }
+
+    impl<S> AsyncRead for BtOwnedReadHalf<S>
+    where
+        for<'a> &'a S: AsyncRead,
+    {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Pin::new(&mut &*self.inner).poll_read(cx, buf)
+        }
+    }
+
+    impl<S> AsyncWrite for BtOwnedWriteHalf<S>
+    where
+        for<'a> &'a S: AsyncWrite,
+    {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut &*self.inner).poll_write(cx, buf)
+        }
+
+        fn poll_write_vectored(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[io::IoSlice<'_>],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut &*self.inner).poll_write_vectored(cx, bufs)
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            (&*self.inner).is_write_vectored()
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut &*self.inner).poll_flush(cx)
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut &*self.inner).poll_shutdown(cx)
+        }
+    }
+}
@@ -0,0 +1,340 @@
+//! Asynchronous Bluetooth sockets for the [Tokio] runtime.
+//!
+//! This module is only available with the `tokio` feature enabled.
+//!
+//! [Tokio]: https://tokio.rs
+
+use std::io;
+use std::net::Shutdown;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use ::tokio::io::unix::AsyncFd;
+use ::tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use super::split;
+use super::{
+    BtOwnedReadHalf, BtOwnedWriteHalf, BtProtocol, BtReadHalf, BtSocketAddr, BtWriteHalf,
+    DiscoveredDevice, DiscoveryOptions,
+};
+use crate::sys::bt::btc as c;
+use crate::sys_common::bt as bt_imp;
+
+/// A Bluetooth socket server, asynchronously listening for connections.
+///
+/// This is the asynchronous counterpart of [`bt::BtListener`].
+///
+/// [`bt::BtListener`]: ../struct.BtListener.html
+#[derive(Debug)]
+pub struct BtListener {
+    inner: AsyncFd<super::BtListener>,
+}
+
+/// An asynchronous Bluetooth stream between a local and remote socket.
+///
+/// This is the asynchronous counterpart of [`bt::BtStream`]. Data is transmitted through
+/// its [`AsyncRead`] and [`AsyncWrite`] implementations, which are also implemented for
+/// `&BtStream` so that reading and writing can happen concurrently.
+///
+/// [`bt::BtStream`]: ../struct.BtStream.html
+/// [`AsyncRead`]: https://docs.rs/tokio/1/tokio/io/trait.AsyncRead.html
+/// [`AsyncWrite`]: https://docs.rs/tokio/1/tokio/io/trait.AsyncWrite.html
+#[derive(Debug)]
+pub struct BtStream {
+    inner: AsyncFd<super::BtStream>,
+}
+
+impl BtListener {
+    /// Creates a new `BtListener` which will be bound to the specified address.
+    ///
+    /// See [`bt::BtListener::bind`] for details.
+    ///
+    /// This function must be called from within a Tokio runtime.
+    ///
+    /// [`bt::BtListener::bind`]: ../struct.BtListener.html#method.bind
+    pub fn bind<'a, I>(addrs: I, protocol: BtProtocol) -> io::Result<Self>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        Self::from_std(super::BtListener::bind(addrs, protocol)?)
+    }
+
+    /// Creates a new `BtListener` from a blocking [`bt::BtListener`], putting it into
+    /// nonblocking mode.
+    ///
+    /// This function must be called from within a Tokio runtime.
+    ///
+    /// [`bt::BtListener`]: ../struct.BtListener.html
+    pub fn from_std(listener: super::BtListener) -> io::Result<Self> {
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            inner: AsyncFd::new(listener)?,
+        })
+    }
+
+    /// Turns this listener back into a blocking [`bt::BtListener`].
+    ///
+    /// [`bt::BtListener`]: ../struct.BtListener.html
+    pub fn into_std(self) -> io::Result<super::BtListener> {
+        let listener = self.inner.into_inner();
+        listener.set_nonblocking(false)?;
+        Ok(listener)
+    }
+
+    /// Accepts a new incoming connection from this listener.
+    ///
+    /// The returned future resolves once a remote device has connected, with the
+    /// corresponding [`BtStream`] and the remote peer's address.
+    ///
+    /// [`BtStream`]: struct.BtStream.html
+    pub async fn accept(&self) -> io::Result<(BtStream, BtSocketAddr)> {
+        loop {
+            let mut guard = self.inner.readable().await?;
+            match guard.try_io(|inner| inner.get_ref().accept()) {
+                Ok(Ok((stream, addr))) => return Ok((BtStream::from_std(stream)?, addr)),
+                Ok(Err(e)) => return Err(e),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    /// Returns the local socket address of this listener.
+    pub fn local_addr(&self) -> io::Result<BtSocketAddr> {
+        self.inner.get_ref().local_addr()
+    }
+
+    /// Returns the socket protocol of this socket.
+    pub fn protocol(&self) -> BtProtocol {
+        self.inner.get_ref().protocol()
+    }
+}
+
+impl BtStream {
+    /// Opens a Bluetooth connection to a remote host.
+    ///
+    /// If `addrs` yields multiple addresses, `connect` will be attempted with each of the
+    /// addresses until the connection succeeds. If none of the addresses succeed, the
+    /// error returned from the last connection attempt (the last address) is returned.
+    ///
+    /// The connection attempt is not limited in time; wrap the returned future in
+    /// `tokio::time::timeout` to limit it.
+    ///
+    /// This function must be called from within a Tokio runtime.
+    pub async fn connect<'a, I>(addrs: I, protocol: BtProtocol) -> io::Result<Self>
+    where
+        I: Iterator<Item = &'a BtSocketAddr>,
+    {
+        let mut last_err = None;
+        for addr in addrs {
+            match Self::connect_addr(addr, protocol).await {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any addresses",
+            )
+        }))
+    }
+
+    async fn connect_addr(addr: &BtSocketAddr, protocol: BtProtocol) -> io::Result<Self> {
+        let (stream, connected) =
+            bt_imp::BtStream::connect_nonblocking(addr, protocol, c::SOCK_STREAM)?;
+        let inner = AsyncFd::new(super::BtStream(stream))?;
+
+        if !connected {
+            // The socket becomes writable once the connection attempt completes, whether
+            // it succeeded or not.
+            let _guard = inner.writable().await?;
+            if let Some(e) = inner.get_ref().take_error()? {
+                return Err(e);
+            }
+        }
+        Ok(Self { inner })
+    }
+
+    /// Creates a new `BtStream` from a blocking [`bt::BtStream`], putting it into
+    /// nonblocking mode.
+    ///
+    /// This function must be called from within a Tokio runtime.
+    ///
+    /// [`bt::BtStream`]: ../struct.BtStream.html
+    pub fn from_std(stream: super::BtStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        Ok(Self {
+            inner: AsyncFd::new(stream)?,
+        })
+    }
+
+    /// Turns this stream back into a blocking [`bt::BtStream`].
+    ///
+    /// [`bt::BtStream`]: ../struct.BtStream.html
+    pub fn into_std(self) -> io::Result<super::BtStream> {
+        let stream = self.inner.into_inner();
+        stream.set_nonblocking(false)?;
+        Ok(stream)
+    }
+
+    /// Returns the socket address that this socket was created from.
+    pub fn local_addr(&self) -> io::Result<BtSocketAddr> {
+        self.inner.get_ref().local_addr()
+    }
+
+    /// Returns the socket address of the remote peer this socket was connected to.
+    pub fn peer_addr(&self) -> io::Result<BtSocketAddr> {
+        self.inner.get_ref().peer_addr()
+    }
+
+    /// Returns the socket protocol of this socket.
+    pub fn protocol(&self) -> BtProtocol {
+        self.inner.get_ref().protocol()
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.inner.get_ref().shutdown(how)
+    }
+
+    /// Splits this stream into a read half and a write half, which can be used
+    /// concurrently from the same task.
+    pub fn split(&self) -> (BtReadHalf<'_, Self>, BtWriteHalf<'_, Self>) {
+        split::split(self)
+    }
+
+    /// Splits this stream into owned read and write halves, which can be moved to
+    /// different tasks.
+    ///
+    /// The halves can be joined back into a `BtStream` with [`BtOwnedReadHalf::reunite`].
+    ///
+    /// [`BtOwnedReadHalf::reunite`]: ../struct.BtOwnedReadHalf.html#method.reunite
+    pub fn into_split(self) -> (BtOwnedReadHalf<Self>, BtOwnedWriteHalf<Self>) {
+        split::into_split(self)
+    }
+}
+
+impl AsyncRead for &BtStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        loop {
+            let mut guard = ready!(self.inner.poll_read_ready(cx))?;
+            let unfilled = buf.initialize_unfilled();
+            match guard.try_io(|inner| inner.get_ref().recv(unfilled)) {
+                Ok(Ok(n)) => {
+                    buf.advance(n);
+                    return Poll::Ready(Ok(()));
+                }
+                Ok(Err(e)) => return Poll::Ready(Err(e)),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
+
+impl AsyncWrite for &BtStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            let mut guard = ready!(self.inner.poll_write_ready(cx))?;
+            match guard.try_io(|inner| inner.get_ref().send(buf)) {
+                Ok(result) => return Poll::Ready(result),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            let mut guard = ready!(self.inner.poll_write_ready(cx))?;
+            match guard.try_io(|inner| inner.get_ref().0.send_vectored(bufs)) {
+                Ok(result) => return Poll::Ready(result),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.inner.get_ref().shutdown(Shutdown::Write))
+    }
+}
+
+impl AsyncRead for BtStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut &*self).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for BtStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &*self).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &*self).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut &*self).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut &*self).poll_shutdown(cx)
+    }
+}
+
+/// Asynchronously discovers nearby Bluetooth devices.
+///
+/// The inquiry is run on Tokio's blocking thread pool, as the underlying OS call blocks
+/// for several seconds. This function must be called from within a Tokio runtime.
+pub async fn discover_devices() -> io::Result<Vec<DiscoveredDevice>> {
+    ::tokio::task::spawn_blocking(super::discover_devices)
+        .await
+        .map_err(io::Error::other)?
+}
+
+/// Asynchronously discovers nearby Bluetooth devices using the given options.
+///
+/// Like [`discover_devices`], the inquiry is run on Tokio's blocking thread pool. This
+/// function must be called from within a Tokio runtime.
+///
+/// [`discover_devices`]: fn.discover_devices.html
+pub async fn discover_devices_with(options: DiscoveryOptions) -> io::Result<Vec<DiscoveredDevice>> {
+    ::tokio::task::spawn_blocking(move || super::discover_devices_with(&options))
+        .await
+        .map_err(io::Error::other)?
+}
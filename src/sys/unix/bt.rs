@@ -1,21 +1,142 @@
 use std::cmp;
-use std::io;
+use std::ffi::CString;
+use std::io::{self, IoSlice, IoSliceMut};
 use std::mem;
 use std::net::Shutdown;
-use std::os::raw::{c_int, c_void};
+use std::os::raw::{c_char, c_int, c_void};
 use std::ptr;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+#[allow(non_camel_case_types)]
 mod libbt {
-    pub use libbluetooth::bluetooth::{bdaddr_t, BTPROTO_L2CAP, BTPROTO_RFCOMM};
-    pub use libbluetooth::hci::{inquiry_info, IREQ_CACHE_FLUSH};
-    pub use libbluetooth::hci_lib::{hci_close_dev, hci_get_route, hci_inquiry, hci_open_dev};
-    pub use libbluetooth::rfcomm::sockaddr_rc;
+    use libbluetooth::bluetooth::bdaddr_t;
+    pub use libbluetooth::bluetooth::{
+        bt_security, bt_voice, BDADDR_BREDR, BDADDR_LE_PUBLIC, BDADDR_LE_RANDOM, BTPROTO_HCI,
+        BTPROTO_L2CAP, BTPROTO_RFCOMM, BTPROTO_SCO, BT_CHANNEL_POLICY,
+        BT_CHANNEL_POLICY_AMP_PREFERRED, BT_CHANNEL_POLICY_BREDR_ONLY,
+        BT_CHANNEL_POLICY_BREDR_PREFERRED, BT_DEFER_SETUP, BT_FLUSHABLE, BT_POWER, BT_RCVMTU,
+        BT_SECURITY, BT_SECURITY_FIPS, BT_SECURITY_HIGH, BT_SECURITY_LOW, BT_SECURITY_MEDIUM,
+        BT_SECURITY_SDP, BT_SNDMTU, BT_VOICE, SOL_BLUETOOTH, SOL_HCI, SOL_L2CAP, SOL_RFCOMM,
+        SOL_SCO,
+    };
+    pub use libbluetooth::hci::{
+        hci_filter, inquiry_info, sockaddr_hci, HCI_CHANNEL_RAW, HCI_FILTER, IREQ_CACHE_FLUSH,
+    };
+    pub use libbluetooth::hci_lib::{
+        hci_close_dev, hci_devid, hci_get_route, hci_inquiry, hci_open_dev, hci_read_link_quality,
+        hci_read_rssi, hci_read_transmit_power_level,
+    };
+    pub use libbluetooth::l2cap::{
+        l2cap_conninfo, l2cap_options, sockaddr_l2, L2CAP_CONNINFO, L2CAP_LM, L2CAP_MODE_BASIC,
+        L2CAP_MODE_ERTM, L2CAP_MODE_STREAMING, L2CAP_OPTIONS,
+    };
+    pub use libbluetooth::rfcomm::{rfcomm_conninfo, sockaddr_rc, RFCOMM_CONNINFO, RFCOMM_LM};
+    pub use libbluetooth::sco::{sco_options, sockaddr_sco, SCO_OPTIONS};
+
+    // Not yet exposed by libbluetooth.
+    pub const BTPROTO_ISO: i32 = 8;
+    pub const BT_PKT_STATUS: i32 = 16;
+    pub const BT_SCM_PKT_STATUS: i32 = 0x03;
+    pub const BT_ISO_QOS: i32 = 17;
+    pub const BT_PKT_SEQNUM: i32 = 22;
+    pub const BT_SCM_PKT_SEQNUM: i32 = 0x05;
+    pub const BT_ISO_QOS_UNSET: u8 = 0xff;
+    pub const BT_ISO_SYNC_TIMEOUT: u16 = 0x07d0;
+    pub const ISO_MAX_NUM_BIS: usize = 0x1f;
+    pub const BT_MODE: i32 = 15;
+    pub const BT_MODE_BASIC: u8 = 0x00;
+    pub const BT_MODE_ERTM: u8 = 0x01;
+    pub const BT_MODE_STREAMING: u8 = 0x02;
+    pub const BT_MODE_LE_FLOWCTL: u8 = 0x03;
+    pub const BT_MODE_EXT_FLOWCTL: u8 = 0x04;
+    pub const L2CAP_MODE_LE_FLOWCTL: u8 = 0x80;
+    pub const L2CAP_MODE_EXT_FLOWCTL: u8 = 0x81;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct sockaddr_iso {
+        pub iso_family: u16,
+        pub iso_bdaddr: bdaddr_t,
+        pub iso_bdaddr_type: u8,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct sockaddr_iso_bc {
+        pub bc_bdaddr: bdaddr_t,
+        pub bc_bdaddr_type: u8,
+        pub bc_sid: u8,
+        pub bc_num_bis: u8,
+        pub bc_bis: [u8; ISO_MAX_NUM_BIS],
+    }
+
+    // A `sockaddr_iso` followed by its flexible `iso_bc` array member, holding a single
+    // element.
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct sockaddr_iso_with_bc {
+        pub iso_family: u16,
+        pub iso_bdaddr: bdaddr_t,
+        pub iso_bdaddr_type: u8,
+        pub iso_bc: sockaddr_iso_bc,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Default)]
+    pub struct bt_iso_io_qos {
+        pub interval: u32,
+        pub latency: u16,
+        pub sdu: u16,
+        pub phy: u8,
+        pub rtn: u8,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct bt_iso_ucast_qos {
+        pub cig: u8,
+        pub cis: u8,
+        pub sca: u8,
+        pub packing: u8,
+        pub framing: u8,
+        pub in_: bt_iso_io_qos,
+        pub out: bt_iso_io_qos,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct bt_iso_bcast_qos {
+        pub big: u8,
+        pub bis: u8,
+        pub sync_factor: u8,
+        pub packing: u8,
+        pub framing: u8,
+        pub in_: bt_iso_io_qos,
+        pub out: bt_iso_io_qos,
+        pub encryption: u8,
+        pub bcode: [u8; 16],
+        pub options: u8,
+        pub skip: u16,
+        pub sync_timeout: u16,
+        pub sync_cte_type: u8,
+        pub mse: u8,
+        pub timeout: u16,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub union bt_iso_qos {
+        pub ucast: bt_iso_ucast_qos,
+        pub bcast: bt_iso_bcast_qos,
+    }
 }
 
 use libc;
 
-use crate::bt::{BtAddr, BtProtocol};
+use crate::bt::{
+    BtAddr, BtAddrType, BtChannelPolicy, BtIsoIoQos, BtIsoQos, BtProtocol, BtSocketAddr, L2capMode,
+    SecurityLevel,
+};
 use crate::sys::fd::FileDesc;
 use crate::sys_common::bt::{getsockopt, setsockopt};
 use crate::sys_common::{AsInner, FromInner, IntoInner};
@@ -35,28 +156,68 @@ use libc::SO_NOSIGPIPE;
 #[cfg(target_os = "linux")]
 const SO_NOSIGPIPE: c_int = 0;
 
+// The maximum number of buffers accepted by `readv` and `sendmsg`.
+const MAX_IOV: usize = libc::UIO_MAXIOV as usize;
+
 pub struct Socket(FileDesc);
 
 impl Socket {
-    pub fn new(protocol: BtProtocol) -> io::Result<Self> {
+    pub fn new(protocol: BtProtocol, ty: c_int) -> io::Result<Self> {
         let protocol = match protocol {
             BtProtocol::L2CAP => libbt::BTPROTO_L2CAP,
             BtProtocol::RFCOMM => libbt::BTPROTO_RFCOMM,
+            BtProtocol::SCO => libbt::BTPROTO_SCO,
+            BtProtocol::ISO => libbt::BTPROTO_ISO,
         };
+        Self::new_raw(protocol, ty)
+    }
+
+    /// Creates a raw HCI socket, bound to the adapter `dev_id`.
+    pub fn new_hci(dev_id: u16) -> io::Result<Self> {
+        let socket = Self::new_raw(libbt::BTPROTO_HCI, libc::SOCK_RAW)?;
+
+        let mut sa: libbt::sockaddr_hci = unsafe { mem::zeroed() };
+        sa.hci_family = libc::AF_BLUETOOTH as u16;
+        sa.hci_dev = dev_id;
+        sa.hci_channel = libbt::HCI_CHANNEL_RAW as u16;
+        let len = mem::size_of_val(&sa) as libc::socklen_t;
+        cvt(unsafe { libc::bind(socket.0.raw(), &sa as *const _ as *const _, len) })?;
+        Ok(socket)
+    }
+
+    /// Returns the protocol of a socket that was created elsewhere, checking that it is a
+    /// Bluetooth socket of a supported protocol.
+    pub fn protocol(&self) -> io::Result<BtProtocol> {
+        let domain: c_int = getsockopt(self, libc::SOL_SOCKET, libc::SO_DOMAIN)?;
+        if domain != libc::AF_BLUETOOTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not a Bluetooth socket",
+            ));
+        }
 
+        let protocol: c_int = getsockopt(self, libc::SOL_SOCKET, libc::SO_PROTOCOL)?;
+        match protocol {
+            libbt::BTPROTO_L2CAP => Ok(BtProtocol::L2CAP),
+            libbt::BTPROTO_RFCOMM => Ok(BtProtocol::RFCOMM),
+            libbt::BTPROTO_SCO => Ok(BtProtocol::SCO),
+            libbt::BTPROTO_ISO => Ok(BtProtocol::ISO),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "unsupported Bluetooth protocol",
+            )),
+        }
+    }
+
+    fn new_raw(protocol: c_int, ty: c_int) -> io::Result<Self> {
         // On linux we first attempt to pass the SOCK_CLOEXEC flag to
         // atomically create the socket and set it as CLOEXEC. Support for
         // this option, however, was added in 2.6.27, and we still support
         // 2.6.18 as a kernel, so if the returned error is EINVAL we
         // fallthrough to the fallback.
         if cfg!(target_os = "linux") {
-            let res = cvt(unsafe {
-                libc::socket(
-                    libc::AF_BLUETOOTH,
-                    libc::SOCK_STREAM | libc::SOCK_CLOEXEC,
-                    protocol,
-                )
-            });
+            let res =
+                cvt(unsafe { libc::socket(libc::AF_BLUETOOTH, ty | libc::SOCK_CLOEXEC, protocol) });
             match res {
                 Ok(fd) => return Ok(Socket(FileDesc::new(fd))),
                 Err(ref e) if e.raw_os_error() == Some(libc::EINVAL) => {}
@@ -64,7 +225,7 @@ impl Socket {
             }
         }
 
-        let fd = cvt(unsafe { libc::socket(libc::AF_BLUETOOTH, libc::SOCK_STREAM, protocol) })?;
+        let fd = cvt(unsafe { libc::socket(libc::AF_BLUETOOTH, ty, protocol) })?;
         let fd = FileDesc::new(fd);
         fd.set_cloexec()?;
         let socket = Socket(fd);
@@ -74,9 +235,9 @@ impl Socket {
         Ok(socket)
     }
 
-    pub fn accept(&self) -> io::Result<(Socket, BtAddr)> {
-        let mut addr: libbt::sockaddr_rc = unsafe { mem::zeroed() };
-        let mut len = mem::size_of::<libbt::sockaddr_rc>() as btc::socklen_t;
+    pub fn accept(&self, protocol: BtProtocol) -> io::Result<(Socket, BtSocketAddr)> {
+        let mut addr: btc::sockaddr_storage = unsafe { mem::zeroed() };
+        let mut len = mem::size_of_val(&addr) as btc::socklen_t;
 
         // Unfortunately the only known way right now to accept a socket and
         // atomically set the CLOEXEC flag is to use the `accept4` syscall on
@@ -92,7 +253,9 @@ impl Socket {
                 )
             });
             match res {
-                Ok(fd) => return Ok((Socket(FileDesc::new(fd)), BtAddr(addr.rc_bdaddr.b))),
+                Ok(fd) => {
+                    return Ok((Socket(FileDesc::new(fd)), sockaddr_to_addr(&addr, protocol)))
+                }
                 Err(ref e) if e.raw_os_error() == Some(libc::ENOSYS) => {}
                 Err(e) => return Err(e),
             }
@@ -103,32 +266,36 @@ impl Socket {
         })?;
         let fd = FileDesc::new(fd);
         fd.set_cloexec()?;
-        Ok((Socket(fd), BtAddr(addr.rc_bdaddr.b)))
+        Ok((Socket(fd), sockaddr_to_addr(&addr, protocol)))
     }
 
-    pub fn connect_timeout(&self, addr: &BtAddr, timeout: Duration) -> io::Result<()> {
+    /// Starts connecting this nonblocking socket.
+    ///
+    /// Returns whether the connection was established immediately. Otherwise, the socket
+    /// becomes writable once the attempt completes, after which `take_error` reports
+    /// whether it failed.
+    pub fn start_connect(&self, addr: &BtSocketAddr, protocol: BtProtocol) -> io::Result<bool> {
+        let (addr, len) = addr_to_sockaddr(addr, protocol);
+        match cvt(unsafe { libc::connect(self.0.raw(), &addr as *const _ as *const _, len) }) {
+            Ok(_) => Ok(true),
+            // There's no ErrorKind for EINPROGRESS
+            Err(ref e) if e.raw_os_error() == Some(libc::EINPROGRESS) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn connect_timeout(
+        &self,
+        addr: &BtSocketAddr,
+        protocol: BtProtocol,
+        timeout: Duration,
+    ) -> io::Result<()> {
         self.set_nonblocking(true)?;
-        let r = {
-            let addr = libbt::sockaddr_rc {
-                rc_family: libc::AF_BLUETOOTH as u16,
-                rc_bdaddr: libbt::bdaddr_t { b: addr.0 },
-                rc_channel: 1,
-            };
-            cvt(unsafe {
-                libc::connect(
-                    self.0.raw(),
-                    &addr as *const _ as *const _,
-                    mem::size_of_val(&addr) as libc::socklen_t,
-                )
-            })
-        };
+        let r = self.start_connect(addr, protocol);
         self.set_nonblocking(false)?;
 
-        match r {
-            Ok(_) => return Ok(()),
-            // There's no ErrorKind for EINPROGRESS
-            Err(ref e) if e.raw_os_error() == Some(libc::EINPROGRESS) => {}
-            Err(e) => return Err(e),
+        if r? {
+            return Ok(());
         }
 
         let mut pollfd = libc::pollfd {
@@ -194,20 +361,33 @@ impl Socket {
         self.recv_with_flags(buf, libc::MSG_PEEK)
     }
 
-    pub fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, BtAddr)> {
-        self.recv_from_with_flags(buf, libc::MSG_PEEK)
+    pub fn peek_from(
+        &self,
+        buf: &mut [u8],
+        protocol: BtProtocol,
+    ) -> io::Result<(usize, BtSocketAddr)> {
+        self.recv_from_with_flags(buf, protocol, libc::MSG_PEEK)
     }
 
     pub fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
         self.recv_with_flags(buf, 0)
     }
 
-    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, BtAddr)> {
-        self.recv_from_with_flags(buf, 0)
+    pub fn recv_from(
+        &self,
+        buf: &mut [u8],
+        protocol: BtProtocol,
+    ) -> io::Result<(usize, BtSocketAddr)> {
+        self.recv_from_with_flags(buf, protocol, 0)
     }
 
-    fn recv_from_with_flags(&self, buf: &mut [u8], flags: c_int) -> io::Result<(usize, BtAddr)> {
-        let mut addr: libbt::sockaddr_rc = unsafe { mem::zeroed() };
+    fn recv_from_with_flags(
+        &self,
+        buf: &mut [u8],
+        protocol: BtProtocol,
+        flags: c_int,
+    ) -> io::Result<(usize, BtSocketAddr)> {
+        let mut addr: btc::sockaddr_storage = unsafe { mem::zeroed() };
         let mut addrlen = mem::size_of_val(&addr) as libc::socklen_t;
 
         let n = cvt(unsafe {
@@ -220,7 +400,22 @@ impl Socket {
                 &mut addrlen,
             )
         })?;
-        Ok((n as usize, BtAddr(addr.rc_bdaddr.b)))
+        Ok((n as usize, sockaddr_to_addr(&addr, protocol)))
+    }
+
+    pub fn peek_trunc(&self, buf: &mut [u8]) -> io::Result<(usize, bool)> {
+        self.recv_trunc_with_flags(buf, libc::MSG_PEEK)
+    }
+
+    pub fn recv_trunc(&self, buf: &mut [u8]) -> io::Result<(usize, bool)> {
+        self.recv_trunc_with_flags(buf, 0)
+    }
+
+    fn recv_trunc_with_flags(&self, buf: &mut [u8], flags: c_int) -> io::Result<(usize, bool)> {
+        // With `MSG_TRUNC`, the real length of the packet is returned, even if it was
+        // longer than the passed buffer.
+        let n = self.recv_with_flags(buf, flags | libc::MSG_TRUNC)?;
+        Ok((cmp::min(n, buf.len()), n > buf.len()))
     }
 
     fn recv_with_flags(&self, buf: &mut [u8], flags: c_int) -> io::Result<usize> {
@@ -235,6 +430,529 @@ impl Socket {
         Ok(ret as usize)
     }
 
+    pub fn read_vectored(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        let ret = cvt(unsafe {
+            libc::readv(
+                self.0.raw(),
+                bufs.as_ptr() as *const libc::iovec,
+                cmp::min(bufs.len(), MAX_IOV) as c_int,
+            )
+        })?;
+        Ok(ret as usize)
+    }
+
+    pub fn send_vectored(&self, bufs: &[IoSlice<'_>], flags: c_int) -> io::Result<usize> {
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_iov = bufs.as_ptr() as *mut libc::iovec;
+        msg.msg_iovlen = cmp::min(bufs.len(), MAX_IOV) as _;
+
+        let ret = cvt(unsafe { libc::sendmsg(self.0.raw(), &msg, flags) })?;
+        Ok(ret as usize)
+    }
+
+    /// Receives a single message, returning whether it was truncated along with its
+    /// receive timestamp and packet status, if reported.
+    pub fn recv_msg(
+        &self,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> io::Result<(usize, bool, Option<SystemTime>, Option<u8>)> {
+        let mut timestamp = None;
+        let mut status = None;
+        let (n, flags) = self.recv_with_cmsgs(bufs, |level, ty, data| {
+            if let Some(ts) = unsafe { cmsg_timestamp(level, ty, data) } {
+                timestamp = Some(ts);
+            } else if level == libbt::SOL_BLUETOOTH && ty == libbt::BT_SCM_PKT_STATUS {
+                status = Some(unsafe { *data });
+            }
+        })?;
+        Ok((n, flags & libc::MSG_TRUNC != 0, timestamp, status))
+    }
+
+    pub fn recv_pkt_status(&self, buf: &mut [u8]) -> io::Result<(usize, Option<u8>)> {
+        let mut status = None;
+        let (n, _) = self.recv_with_cmsgs(&mut [IoSliceMut::new(buf)], |level, ty, data| {
+            if level == libbt::SOL_BLUETOOTH && ty == libbt::BT_SCM_PKT_STATUS {
+                status = Some(unsafe { *data });
+            }
+        })?;
+        Ok((n, status))
+    }
+
+    pub fn recv_iso(&self, buf: &mut [u8]) -> io::Result<(usize, Option<SystemTime>, Option<u16>)> {
+        let mut timestamp = None;
+        let mut seqnum = None;
+        let (n, _) = self.recv_with_cmsgs(&mut [IoSliceMut::new(buf)], |level, ty, data| {
+            if let Some(ts) = unsafe { cmsg_timestamp(level, ty, data) } {
+                timestamp = Some(ts);
+            } else if level == libbt::SOL_BLUETOOTH && ty == libbt::BT_SCM_PKT_SEQNUM {
+                seqnum = Some(unsafe { ptr::read_unaligned(data as *const u16) });
+            }
+        })?;
+        Ok((n, timestamp, seqnum))
+    }
+
+    /// Receives a single packet with `recvmsg`, calling `f` with the level, type and a
+    /// pointer to the data of each control message received along with it.
+    ///
+    /// Returns the number of bytes read and the flags of the received message.
+    fn recv_with_cmsgs<F>(
+        &self,
+        bufs: &mut [IoSliceMut<'_>],
+        mut f: F,
+    ) -> io::Result<(usize, c_int)>
+    where
+        F: FnMut(c_int, c_int, *const u8),
+    {
+        // Use `u64`s to guarantee the alignment required for `cmsghdr`.
+        let mut control = [0u64; 16];
+
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_iov = bufs.as_mut_ptr() as *mut libc::iovec;
+        msg.msg_iovlen = cmp::min(bufs.len(), MAX_IOV) as _;
+        msg.msg_control = control.as_mut_ptr() as *mut c_void;
+        msg.msg_controllen = mem::size_of_val(&control) as _;
+
+        let n = cvt(unsafe { libc::recvmsg(self.0.raw(), &mut msg, 0) })?;
+
+        unsafe {
+            let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+            while !cmsg.is_null() {
+                f((*cmsg).cmsg_level, (*cmsg).cmsg_type, libc::CMSG_DATA(cmsg));
+                cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+            }
+        }
+        Ok((n as usize, msg.msg_flags))
+    }
+
+    pub fn set_pkt_status(&self, enabled: bool) -> io::Result<()> {
+        setsockopt(
+            self,
+            libbt::SOL_BLUETOOTH,
+            libbt::BT_PKT_STATUS,
+            enabled as c_int,
+        )
+    }
+
+    pub fn pkt_status(&self) -> io::Result<bool> {
+        let raw: c_int = getsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_PKT_STATUS)?;
+        Ok(raw != 0)
+    }
+
+    pub fn set_voice(&self, setting: u16) -> io::Result<()> {
+        setsockopt(
+            self,
+            libbt::SOL_BLUETOOTH,
+            libbt::BT_VOICE,
+            libbt::bt_voice { setting },
+        )
+    }
+
+    pub fn voice(&self) -> io::Result<u16> {
+        let raw: libbt::bt_voice = getsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_VOICE)?;
+        Ok(raw.setting)
+    }
+
+    pub fn sco_mtu(&self) -> io::Result<u16> {
+        let raw: libbt::sco_options = getsockopt(self, libbt::SOL_SCO, libbt::SCO_OPTIONS)?;
+        Ok(raw.mtu)
+    }
+
+    pub fn set_recv_mtu(&self, mtu: u16) -> io::Result<()> {
+        setsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_RCVMTU, mtu)
+    }
+
+    // The kernel derives the MPS of LE connection-oriented channels from their MTU and
+    // the LE link, without a socket option to set it.
+    pub fn set_le_mps(&self, _mps: u16) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Configuring the MPS of LE connection-oriented channels is currently not supported on Linux",
+        ))
+    }
+
+    // The kernel derives the initial credits of LE connection-oriented channels from their
+    // MTU, MPS and receive buffer, without a socket option to set them.
+    pub fn set_le_credits(&self, _credits: u16) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Configuring the initial credits of LE connection-oriented channels is currently not supported on Linux",
+        ))
+    }
+
+    pub fn set_l2cap_recv_mtu(&self, mtu: u16) -> io::Result<()> {
+        // `L2CAP_OPTIONS` is rejected with `EINVAL` on LE sockets, which only support
+        // `BT_RCVMTU`.
+        match getsockopt(self, libbt::SOL_L2CAP, libbt::L2CAP_OPTIONS) {
+            Ok(opts) => {
+                let opts = libbt::l2cap_options { imtu: mtu, ..opts };
+                setsockopt(self, libbt::SOL_L2CAP, libbt::L2CAP_OPTIONS, opts)
+            }
+            Err(ref e) if e.raw_os_error() == Some(libc::EINVAL) => self.set_recv_mtu(mtu),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn l2cap_mtu(&self) -> io::Result<(u16, u16)> {
+        match getsockopt::<libbt::l2cap_options>(self, libbt::SOL_L2CAP, libbt::L2CAP_OPTIONS) {
+            Ok(opts) => Ok((opts.imtu, opts.omtu)),
+            Err(ref e) if e.raw_os_error() == Some(libc::EINVAL) => {
+                let recv = getsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_RCVMTU)?;
+                let send = getsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_SNDMTU)?;
+                Ok((recv, send))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn set_l2cap_mode(&self, mode: L2capMode) -> io::Result<()> {
+        let raw = match mode {
+            L2capMode::Basic => libbt::BT_MODE_BASIC,
+            L2capMode::Ertm => libbt::BT_MODE_ERTM,
+            L2capMode::Streaming => libbt::BT_MODE_STREAMING,
+            L2capMode::LeFlowControl => libbt::BT_MODE_LE_FLOWCTL,
+            L2capMode::ExtFlowControl => libbt::BT_MODE_EXT_FLOWCTL,
+        };
+        match setsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_MODE, raw) {
+            // `BT_MODE` is only available when the kernel has enhanced credit based flow
+            // control enabled. Without it, the BR/EDR modes are set through
+            // `L2CAP_OPTIONS` instead.
+            Err(ref e) if e.raw_os_error() == Some(libc::ENOPROTOOPT) => {
+                let mode = match mode {
+                    L2capMode::Basic => libbt::L2CAP_MODE_BASIC,
+                    L2capMode::Ertm => libbt::L2CAP_MODE_ERTM,
+                    L2capMode::Streaming => libbt::L2CAP_MODE_STREAMING,
+                    L2capMode::LeFlowControl | L2capMode::ExtFlowControl => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "credit based flow control modes are not enabled in the kernel",
+                        ))
+                    }
+                };
+                let opts: libbt::l2cap_options =
+                    getsockopt(self, libbt::SOL_L2CAP, libbt::L2CAP_OPTIONS)?;
+                let opts = libbt::l2cap_options {
+                    mode: mode as u8,
+                    ..opts
+                };
+                setsockopt(self, libbt::SOL_L2CAP, libbt::L2CAP_OPTIONS, opts)
+            }
+            r => r,
+        }
+    }
+
+    pub fn l2cap_mode(&self) -> io::Result<L2capMode> {
+        match getsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_MODE) {
+            Ok(raw) => Ok(l2cap_mode_from_bt_mode(raw)),
+            Err(ref e) if e.raw_os_error() == Some(libc::ENOPROTOOPT) => {
+                match getsockopt::<libbt::l2cap_options>(
+                    self,
+                    libbt::SOL_L2CAP,
+                    libbt::L2CAP_OPTIONS,
+                ) {
+                    Ok(opts) => Ok(l2cap_mode_from_raw(opts.mode)),
+                    // LE sockets without `BT_MODE` always use LE flow control.
+                    Err(ref e) if e.raw_os_error() == Some(libc::EINVAL) => {
+                        Ok(L2capMode::LeFlowControl)
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn bind_iso_broadcast(
+        &self,
+        addr: &BtSocketAddr,
+        broadcaster: &BtSocketAddr,
+        sid: u8,
+        bis: &[u8],
+    ) -> io::Result<()> {
+        if bis.len() > libbt::ISO_MAX_NUM_BIS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "too many broadcast isochronous streams",
+            ));
+        }
+
+        let mut bc_bis = [0; libbt::ISO_MAX_NUM_BIS];
+        bc_bis[..bis.len()].copy_from_slice(bis);
+
+        let mut sa: libbt::sockaddr_iso_with_bc = unsafe { mem::zeroed() };
+        sa.iso_family = libc::AF_BLUETOOTH as u16;
+        sa.iso_bdaddr.b = addr.addr().0;
+        sa.iso_bdaddr_type = addr_type_to_raw(addr.addr_type());
+        sa.iso_bc.bc_bdaddr.b = broadcaster.addr().0;
+        sa.iso_bc.bc_bdaddr_type = addr_type_to_raw(broadcaster.addr_type());
+        sa.iso_bc.bc_sid = sid;
+        sa.iso_bc.bc_num_bis = bis.len() as u8;
+        sa.iso_bc.bc_bis = bc_bis;
+
+        let len = mem::size_of_val(&sa) as libc::socklen_t;
+        cvt(unsafe { libc::bind(self.0.raw(), &sa as *const _ as *const _, len) })?;
+        Ok(())
+    }
+
+    pub fn set_iso_qos(&self, qos: &BtIsoQos, broadcast: bool) -> io::Result<()> {
+        let framing = qos.framed as u8;
+        let raw = if broadcast {
+            let (encryption, bcode) = match qos.broadcast_code {
+                Some(code) => (1, code),
+                None => (0, [0; 16]),
+            };
+            libbt::bt_iso_qos {
+                bcast: libbt::bt_iso_bcast_qos {
+                    big: qos.group.unwrap_or(libbt::BT_ISO_QOS_UNSET),
+                    bis: qos.stream.unwrap_or(libbt::BT_ISO_QOS_UNSET),
+                    sync_factor: 0x07,
+                    packing: 0,
+                    framing,
+                    in_: iso_io_qos_to_raw(&qos.input),
+                    out: iso_io_qos_to_raw(&qos.output),
+                    encryption,
+                    bcode,
+                    options: 0,
+                    skip: 0,
+                    sync_timeout: libbt::BT_ISO_SYNC_TIMEOUT,
+                    sync_cte_type: 0,
+                    mse: 0,
+                    timeout: libbt::BT_ISO_SYNC_TIMEOUT,
+                },
+            }
+        } else {
+            libbt::bt_iso_qos {
+                ucast: libbt::bt_iso_ucast_qos {
+                    cig: qos.group.unwrap_or(libbt::BT_ISO_QOS_UNSET),
+                    cis: qos.stream.unwrap_or(libbt::BT_ISO_QOS_UNSET),
+                    sca: 0,
+                    packing: 0,
+                    framing,
+                    in_: iso_io_qos_to_raw(&qos.input),
+                    out: iso_io_qos_to_raw(&qos.output),
+                },
+            }
+        };
+        setsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_ISO_QOS, raw)
+    }
+
+    pub fn iso_qos(&self) -> io::Result<BtIsoQos> {
+        let raw: libbt::bt_iso_qos = getsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_ISO_QOS)?;
+        // The identifiers, framing and I/O parameters share the same layout in the unicast
+        // and broadcast variants.
+        let raw = unsafe { raw.ucast };
+        let id = |id| {
+            if id == libbt::BT_ISO_QOS_UNSET {
+                None
+            } else {
+                Some(id)
+            }
+        };
+        Ok(BtIsoQos {
+            group: id(raw.cig),
+            stream: id(raw.cis),
+            framed: raw.framing != 0,
+            input: iso_io_qos_from_raw(&raw.in_),
+            output: iso_io_qos_from_raw(&raw.out),
+            broadcast_code: None,
+        })
+    }
+
+    pub fn set_timestamps(&self, enabled: bool) -> io::Result<()> {
+        setsockopt(
+            self,
+            libc::SOL_SOCKET,
+            libc::SO_TIMESTAMPNS,
+            enabled as c_int,
+        )
+    }
+
+    pub fn timestamps(&self) -> io::Result<bool> {
+        let raw: c_int = getsockopt(self, libc::SOL_SOCKET, libc::SO_TIMESTAMPNS)?;
+        Ok(raw != 0)
+    }
+
+    pub fn set_pkt_seqnum(&self, enabled: bool) -> io::Result<()> {
+        setsockopt(
+            self,
+            libbt::SOL_BLUETOOTH,
+            libbt::BT_PKT_SEQNUM,
+            enabled as c_int,
+        )
+    }
+
+    pub fn pkt_seqnum(&self) -> io::Result<bool> {
+        let raw: c_int = getsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_PKT_SEQNUM)?;
+        Ok(raw != 0)
+    }
+
+    pub fn set_defer_setup(&self, enabled: bool) -> io::Result<()> {
+        setsockopt(
+            self,
+            libbt::SOL_BLUETOOTH,
+            libbt::BT_DEFER_SETUP,
+            enabled as u32,
+        )
+    }
+
+    pub fn defer_setup(&self) -> io::Result<bool> {
+        let raw: u32 = getsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_DEFER_SETUP)?;
+        Ok(raw != 0)
+    }
+
+    /// Completes the setup of a connection accepted with `BT_DEFER_SETUP`, waiting until
+    /// it is established.
+    pub fn authorize_deferred(&self) -> io::Result<()> {
+        // The first read on a deferred socket accepts the connection without consuming
+        // any data.
+        let mut buf = [0; 1];
+        self.read(&mut buf)?;
+
+        let mut pollfd = libc::pollfd {
+            fd: self.0.raw(),
+            events: libc::POLLOUT,
+            revents: 0,
+        };
+        loop {
+            if unsafe { libc::poll(&mut pollfd, 1, -1) } == -1 {
+                let err = io::Error::last_os_error();
+                if err.kind() != io::ErrorKind::Interrupted {
+                    return Err(err);
+                }
+            } else if pollfd.revents & libc::POLLHUP != 0 {
+                return Err(self.take_error()?.unwrap_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::ConnectionAborted,
+                        "connection closed during setup",
+                    )
+                }));
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    pub fn conninfo(&self, protocol: BtProtocol) -> io::Result<(u16, [u8; 3])> {
+        match protocol {
+            BtProtocol::L2CAP => {
+                let raw: libbt::l2cap_conninfo =
+                    getsockopt(self, libbt::SOL_L2CAP, libbt::L2CAP_CONNINFO)?;
+                Ok((raw.hci_handle, raw.dev_class))
+            }
+            BtProtocol::RFCOMM => {
+                let raw: libbt::rfcomm_conninfo =
+                    getsockopt(self, libbt::SOL_RFCOMM, libbt::RFCOMM_CONNINFO)?;
+                Ok((raw.hci_handle, raw.dev_class))
+            }
+            BtProtocol::SCO | BtProtocol::ISO => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "connection information is only available for L2CAP and RFCOMM",
+            )),
+        }
+    }
+
+    pub fn set_link_mode(&self, protocol: BtProtocol, mode: u32) -> io::Result<()> {
+        let (level, name) = link_mode_opt(protocol)?;
+        setsockopt(self, level, name, mode)
+    }
+
+    pub fn link_mode(&self, protocol: BtProtocol) -> io::Result<u32> {
+        let (level, name) = link_mode_opt(protocol)?;
+        getsockopt(self, level, name)
+    }
+
+    pub fn set_force_active(&self, enabled: bool) -> io::Result<()> {
+        // `struct bt_power` consists of the single `force_active` byte.
+        setsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_POWER, enabled as u8)
+    }
+
+    pub fn force_active(&self) -> io::Result<bool> {
+        let raw: u8 = getsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_POWER)?;
+        Ok(raw != 0)
+    }
+
+    pub fn set_flushable(&self, enabled: bool) -> io::Result<()> {
+        setsockopt(
+            self,
+            libbt::SOL_BLUETOOTH,
+            libbt::BT_FLUSHABLE,
+            enabled as u32,
+        )
+    }
+
+    pub fn flushable(&self) -> io::Result<bool> {
+        let raw: u32 = getsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_FLUSHABLE)?;
+        Ok(raw != 0)
+    }
+
+    pub fn set_channel_policy(&self, policy: BtChannelPolicy) -> io::Result<()> {
+        let raw = match policy {
+            BtChannelPolicy::BrEdrOnly => libbt::BT_CHANNEL_POLICY_BREDR_ONLY,
+            BtChannelPolicy::BrEdrPreferred => libbt::BT_CHANNEL_POLICY_BREDR_PREFERRED,
+            BtChannelPolicy::AmpPreferred => libbt::BT_CHANNEL_POLICY_AMP_PREFERRED,
+        };
+        setsockopt(
+            self,
+            libbt::SOL_BLUETOOTH,
+            libbt::BT_CHANNEL_POLICY,
+            raw as u32,
+        )
+    }
+
+    pub fn channel_policy(&self) -> io::Result<BtChannelPolicy> {
+        let raw: u32 = getsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_CHANNEL_POLICY)?;
+        Ok(match raw as c_int {
+            libbt::BT_CHANNEL_POLICY_BREDR_PREFERRED => BtChannelPolicy::BrEdrPreferred,
+            libbt::BT_CHANNEL_POLICY_AMP_PREFERRED => BtChannelPolicy::AmpPreferred,
+            _ => BtChannelPolicy::BrEdrOnly,
+        })
+    }
+
+    pub fn set_security(&self, level: SecurityLevel) -> io::Result<()> {
+        let level = match level {
+            SecurityLevel::Sdp => libbt::BT_SECURITY_SDP,
+            SecurityLevel::Low => libbt::BT_SECURITY_LOW,
+            SecurityLevel::Medium => libbt::BT_SECURITY_MEDIUM,
+            SecurityLevel::High => libbt::BT_SECURITY_HIGH,
+            SecurityLevel::Fips => libbt::BT_SECURITY_FIPS,
+        };
+        let sec = libbt::bt_security {
+            level: level as u8,
+            key_size: 0,
+        };
+        setsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_SECURITY, sec)
+    }
+
+    pub fn security(&self) -> io::Result<(SecurityLevel, u8)> {
+        let raw: libbt::bt_security = getsockopt(self, libbt::SOL_BLUETOOTH, libbt::BT_SECURITY)?;
+        let level = match c_int::from(raw.level) {
+            libbt::BT_SECURITY_SDP => SecurityLevel::Sdp,
+            libbt::BT_SECURITY_LOW => SecurityLevel::Low,
+            libbt::BT_SECURITY_MEDIUM => SecurityLevel::Medium,
+            libbt::BT_SECURITY_HIGH => SecurityLevel::High,
+            _ => SecurityLevel::Fips,
+        };
+        Ok((level, raw.key_size))
+    }
+
+    pub fn set_hci_filter(
+        &self,
+        type_mask: u32,
+        event_mask: [u32; 2],
+        opcode: u16,
+    ) -> io::Result<()> {
+        let filter = libbt::hci_filter {
+            type_mask,
+            event_mask,
+            opcode: opcode.to_le(),
+        };
+        setsockopt(self, libbt::SOL_HCI, libbt::HCI_FILTER, filter)
+    }
+
+    pub fn hci_filter(&self) -> io::Result<(u32, [u32; 2], u16)> {
+        let raw: libbt::hci_filter = getsockopt(self, libbt::SOL_HCI, libbt::HCI_FILTER)?;
+        Ok((raw.type_mask, raw.event_mask, u16::from_le(raw.opcode)))
+    }
+
     pub fn set_timeout(&self, dur: Option<Duration>, kind: c_int) -> io::Result<()> {
         let timeout = match dur {
             Some(dur) => {
@@ -325,8 +1043,135 @@ impl IntoInner<c_int> for Socket {
     }
 }
 
-pub fn discover_devices() -> io::Result<Vec<BtAddr>> {
+/// Maps the errors reported by the kernel when a link fails to meet its required
+/// security to distinct error kinds.
+///
+/// Authentication and key failures become `PermissionDenied`, wrapping the original
+/// error so that its OS error code stays reachable. A remote device refusing the
+/// connection for lack of security is reported as `ECONNREFUSED`, which already maps
+/// to `ConnectionRefused`.
+pub fn security_error(err: io::Error) -> io::Error {
+    match err.raw_os_error() {
+        Some(libc::EACCES)
+        | Some(libc::EKEYREJECTED)
+        | Some(libc::EKEYEXPIRED)
+        | Some(libc::EKEYREVOKED) => io::Error::new(io::ErrorKind::PermissionDenied, err),
+        _ => err,
+    }
+}
+
+/// Returns whether `err` reports that the link to the remote device was lost.
+pub fn is_link_loss(err: &io::Error) -> bool {
+    matches!(
+        err.raw_os_error(),
+        Some(libc::ECONNRESET)
+            | Some(libc::ECONNABORTED)
+            | Some(libc::EPIPE)
+            | Some(libc::ENOTCONN)
+            | Some(libc::ETIMEDOUT)
+            | Some(libc::EHOSTDOWN)
+            | Some(libc::EHOSTUNREACH)
+    )
+}
+
+/// Returns whether a connection attempt failed with `err` for a reason that may go away,
+/// such as the remote device being out of range or busy.
+pub fn is_transient_connect_error(err: &io::Error) -> bool {
+    is_link_loss(err)
+        || err.kind() == io::ErrorKind::TimedOut
+        || matches!(
+            err.raw_os_error(),
+            Some(libc::ECONNREFUSED) | Some(libc::EBUSY) | Some(libc::EAGAIN)
+        )
+}
+
+pub fn default_hci_dev() -> io::Result<u16> {
     let device_id = unsafe { libbt::hci_get_route(ptr::null_mut()) };
+    if device_id == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(device_id as u16)
+    }
+}
+
+/// Returns the id of the local HCI device with the given address.
+pub fn hci_dev_for_addr(addr: &BtAddr) -> io::Result<u16> {
+    let addr = CString::new(addr.to_string()).unwrap();
+    let device_id = unsafe { libbt::hci_devid(addr.as_ptr()) };
+    if device_id == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(device_id as u16)
+    }
+}
+
+pub fn read_rssi(dev_id: u16, handle: u16) -> io::Result<i8> {
+    hci_read(dev_id, |dd, rssi: *mut i8| unsafe {
+        libbt::hci_read_rssi(dd, handle, rssi.cast(), HCI_READ_TIMEOUT)
+    })
+}
+
+pub fn read_transmit_power_level(dev_id: u16, handle: u16, max: bool) -> io::Result<i8> {
+    // The type parameter selects the current (0) or maximum (1) power level.
+    hci_read(dev_id, |dd, level: *mut i8| unsafe {
+        libbt::hci_read_transmit_power_level(
+            dd,
+            handle,
+            max as c_char,
+            level.cast(),
+            HCI_READ_TIMEOUT,
+        )
+    })
+}
+
+pub fn read_link_quality(dev_id: u16, handle: u16) -> io::Result<u8> {
+    hci_read(dev_id, |dd, quality: *mut u8| unsafe {
+        libbt::hci_read_link_quality(dd, handle, quality.cast(), HCI_READ_TIMEOUT)
+    })
+}
+
+const HCI_READ_TIMEOUT: c_int = 1000; // milliseconds
+
+/// Opens the HCI device `dev_id` and runs a single request on it.
+fn hci_read<T, F>(dev_id: u16, f: F) -> io::Result<T>
+where
+    T: Default,
+    F: FnOnce(c_int, *mut T) -> c_int,
+{
+    let dd = unsafe { libbt::hci_open_dev(c_int::from(dev_id)) };
+    if dd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut value = T::default();
+    let result = if f(dd, &mut value) < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(value)
+    };
+    unsafe { libbt::hci_close_dev(dd) };
+    result
+}
+
+/// The address, raw class of device, page scan repetition mode and clock offset of a
+/// device that responded to an inquiry.
+pub type InquiryResult = (BtAddr, [u8; 3], Option<u8>, Option<u16>);
+
+/// Runs an inquiry on the given adapter, or the default one, returning each device that
+/// responded.
+///
+/// `length` is in units of 1.28 seconds, and a `max_responses` of 0 means no limit.
+pub fn inquiry(
+    adapter: Option<&BtAddr>,
+    length: u8,
+    max_responses: u8,
+    flush_cache: bool,
+    lap: [u8; 3],
+) -> io::Result<Vec<InquiryResult>> {
+    let device_id = match adapter {
+        Some(addr) => c_int::from(hci_dev_for_addr(addr)?),
+        None => unsafe { libbt::hci_get_route(ptr::null_mut()) },
+    };
     if device_id == -1 {
         return Err(io::Error::last_os_error());
     }
@@ -336,25 +1181,47 @@ pub fn discover_devices() -> io::Result<Vec<BtAddr>> {
         return Err(io::Error::last_os_error());
     }
 
-    let mut inquiry_infos = vec![libbt::inquiry_info::default(); 256];
+    // The controller reports at most 255 devices when no limit is given.
+    let capacity = if max_responses == 0 {
+        255
+    } else {
+        max_responses
+    };
+    let mut inquiry_infos = vec![libbt::inquiry_info::default(); usize::from(capacity)];
 
-    const TIMEOUT: c_int = 4; // 4 * 1.28 seconds
+    let flags = if flush_cache {
+        libbt::IREQ_CACHE_FLUSH
+    } else {
+        0
+    };
     let num_responses = unsafe {
         libbt::hci_inquiry(
             device_id,
-            TIMEOUT,
-            inquiry_infos.len() as c_int,
-            ptr::null(),
+            c_int::from(length),
+            c_int::from(max_responses),
+            lap.as_ptr().cast(),
             &mut inquiry_infos.as_mut_ptr(),
-            libbt::IREQ_CACHE_FLUSH,
+            flags,
         )
     };
     if num_responses == -1 {
-        return Err(io::Error::last_os_error());
+        let err = io::Error::last_os_error();
+        unsafe { libbt::hci_close_dev(local_socket) };
+        return Err(err);
     }
 
     inquiry_infos.truncate(num_responses as usize);
-    let devices = inquiry_infos.iter().map(|ii| BtAddr(ii.bdaddr.b)).collect();
+    let devices = inquiry_infos
+        .iter()
+        .map(|ii| {
+            (
+                BtAddr(ii.bdaddr.b),
+                ii.dev_class,
+                Some(ii.pscan_rep_mode),
+                Some(u16::from_le(ii.clock_offset)),
+            )
+        })
+        .collect();
 
     if -1 == unsafe { libbt::hci_close_dev(local_socket) } {
         Err(io::Error::last_os_error())
@@ -363,22 +1230,249 @@ pub fn discover_devices() -> io::Result<Vec<BtAddr>> {
     }
 }
 
-impl<'a> Into<BtAddr> for &'a btc::sockaddr_storage {
-    fn into(self) -> BtAddr {
-        let addr: &'a libbt::sockaddr_rc = unsafe { &*(self as *const _ as *const _) };
-        BtAddr(addr.rc_bdaddr.b)
+/// Decodes the receive timestamp carried by a `SCM_TIMESTAMP`, `SCM_TIMESTAMPNS` or
+/// `SCM_TIMESTAMPING` control message.
+unsafe fn cmsg_timestamp(level: c_int, ty: c_int, data: *const u8) -> Option<SystemTime> {
+    if level != libc::SOL_SOCKET {
+        return None;
     }
+    let since_epoch = match ty {
+        libc::SCM_TIMESTAMP => {
+            let tv: libc::timeval = ptr::read_unaligned(data as *const _);
+            Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1000)
+        }
+        libc::SCM_TIMESTAMPNS => {
+            let ts: libc::timespec = ptr::read_unaligned(data as *const _);
+            Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+        }
+        libc::SCM_TIMESTAMPING => {
+            // Software, legacy and hardware timestamps, of which only the enabled ones
+            // are non-zero.
+            let ts: [libc::timespec; 3] = ptr::read_unaligned(data as *const _);
+            let ts = ts.iter().find(|ts| ts.tv_sec != 0 || ts.tv_nsec != 0)?;
+            Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+        }
+        _ => return None,
+    };
+    Some(UNIX_EPOCH + since_epoch)
 }
 
-impl<'a> Into<(btc::sockaddr_storage, btc::socklen_t)> for &'a BtAddr {
-    fn into(self) -> (btc::sockaddr_storage, btc::socklen_t) {
-        let mut addr: btc::sockaddr_storage = unsafe { mem::zeroed() };
+fn addr_type_to_raw(addr_type: BtAddrType) -> u8 {
+    let raw = match addr_type {
+        BtAddrType::BrEdr => libbt::BDADDR_BREDR,
+        BtAddrType::LePublic => libbt::BDADDR_LE_PUBLIC,
+        BtAddrType::LeRandom => libbt::BDADDR_LE_RANDOM,
+    };
+    raw as u8
+}
+
+fn addr_type_from_raw(raw: u8) -> BtAddrType {
+    match c_int::from(raw) {
+        libbt::BDADDR_LE_PUBLIC => BtAddrType::LePublic,
+        libbt::BDADDR_LE_RANDOM => BtAddrType::LeRandom,
+        _ => BtAddrType::BrEdr,
+    }
+}
+
+fn link_mode_opt(protocol: BtProtocol) -> io::Result<(c_int, c_int)> {
+    match protocol {
+        BtProtocol::L2CAP => Ok((libbt::SOL_L2CAP, libbt::L2CAP_LM)),
+        BtProtocol::RFCOMM => Ok((libbt::SOL_RFCOMM, libbt::RFCOMM_LM)),
+        BtProtocol::SCO | BtProtocol::ISO => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "link mode flags are only available for L2CAP and RFCOMM",
+        )),
+    }
+}
+
+fn l2cap_mode_from_bt_mode(raw: u8) -> L2capMode {
+    match raw {
+        libbt::BT_MODE_ERTM => L2capMode::Ertm,
+        libbt::BT_MODE_STREAMING => L2capMode::Streaming,
+        libbt::BT_MODE_LE_FLOWCTL => L2capMode::LeFlowControl,
+        libbt::BT_MODE_EXT_FLOWCTL => L2capMode::ExtFlowControl,
+        _ => L2capMode::Basic,
+    }
+}
+
+fn l2cap_mode_from_raw(raw: u8) -> L2capMode {
+    match raw {
+        libbt::L2CAP_MODE_LE_FLOWCTL => L2capMode::LeFlowControl,
+        libbt::L2CAP_MODE_EXT_FLOWCTL => L2capMode::ExtFlowControl,
+        raw if c_int::from(raw) == libbt::L2CAP_MODE_ERTM => L2capMode::Ertm,
+        raw if c_int::from(raw) == libbt::L2CAP_MODE_STREAMING => L2capMode::Streaming,
+        _ => L2capMode::Basic,
+    }
+}
+
+fn iso_io_qos_to_raw(qos: &BtIsoIoQos) -> libbt::bt_iso_io_qos {
+    libbt::bt_iso_io_qos {
+        interval: qos.interval,
+        latency: qos.latency,
+        sdu: qos.sdu,
+        phy: qos.phy,
+        rtn: qos.rtn,
+    }
+}
+
+fn iso_io_qos_from_raw(raw: &libbt::bt_iso_io_qos) -> BtIsoIoQos {
+    BtIsoIoQos {
+        interval: raw.interval,
+        latency: raw.latency,
+        sdu: raw.sdu,
+        phy: raw.phy,
+        rtn: raw.rtn,
+    }
+}
+
+pub fn sockaddr_to_addr(storage: &btc::sockaddr_storage, protocol: BtProtocol) -> BtSocketAddr {
+    match protocol {
+        BtProtocol::L2CAP => {
+            let sal2: &libbt::sockaddr_l2 = unsafe { &*(storage as *const _ as *const _) };
+            let mut addr = BtSocketAddr::l2cap(BtAddr(sal2.l2_bdaddr.b), u16::from_le(sal2.l2_psm));
+            addr.set_cid(u16::from_le(sal2.l2_cid));
+            addr.set_addr_type(addr_type_from_raw(sal2.l2_bdaddr_type));
+            addr
+        }
+        BtProtocol::RFCOMM => {
+            let sarc: &libbt::sockaddr_rc = unsafe { &*(storage as *const _ as *const _) };
+            BtSocketAddr::rfcomm(BtAddr(sarc.rc_bdaddr.b), sarc.rc_channel)
+        }
+        BtProtocol::SCO => {
+            let sasco: &libbt::sockaddr_sco = unsafe { &*(storage as *const _ as *const _) };
+            BtSocketAddr::new(BtAddr(sasco.sco_bdaddr.b))
+        }
+        BtProtocol::ISO => {
+            let saiso: &libbt::sockaddr_iso = unsafe { &*(storage as *const _ as *const _) };
+            let mut addr = BtSocketAddr::new(BtAddr(saiso.iso_bdaddr.b));
+            addr.set_addr_type(addr_type_from_raw(saiso.iso_bdaddr_type));
+            addr
+        }
+    }
+}
+
+pub fn addr_to_sockaddr(
+    addr: &BtSocketAddr,
+    protocol: BtProtocol,
+) -> (btc::sockaddr_storage, btc::socklen_t) {
+    let mut storage: btc::sockaddr_storage = unsafe { mem::zeroed() };
+
+    let len = match protocol {
+        BtProtocol::L2CAP => {
+            let sal2: &mut libbt::sockaddr_l2 = unsafe { &mut *(&mut storage as *mut _ as *mut _) };
+            sal2.l2_family = libc::AF_BLUETOOTH as u16;
+            // The PSM and CID are transmitted in little-endian byte order.
+            sal2.l2_psm = addr.psm().to_le();
+            sal2.l2_bdaddr.b = addr.addr().0;
+            sal2.l2_cid = addr.cid().to_le();
+            sal2.l2_bdaddr_type = addr_type_to_raw(addr.addr_type());
+            mem::size_of::<libbt::sockaddr_l2>()
+        }
+        BtProtocol::RFCOMM => {
+            let sarc: &mut libbt::sockaddr_rc = unsafe { &mut *(&mut storage as *mut _ as *mut _) };
+            sarc.rc_family = libc::AF_BLUETOOTH as u16;
+            sarc.rc_bdaddr.b = addr.addr().0;
+            sarc.rc_channel = addr.channel();
+            mem::size_of::<libbt::sockaddr_rc>()
+        }
+        BtProtocol::SCO => {
+            let sasco: &mut libbt::sockaddr_sco =
+                unsafe { &mut *(&mut storage as *mut _ as *mut _) };
+            sasco.sco_family = libc::AF_BLUETOOTH as u16;
+            sasco.sco_bdaddr.b = addr.addr().0;
+            mem::size_of::<libbt::sockaddr_sco>()
+        }
+        BtProtocol::ISO => {
+            let saiso: &mut libbt::sockaddr_iso =
+                unsafe { &mut *(&mut storage as *mut _ as *mut _) };
+            saiso.iso_family = libc::AF_BLUETOOTH as u16;
+            saiso.iso_bdaddr.b = addr.addr().0;
+            saiso.iso_bdaddr_type = addr_type_to_raw(addr.addr_type());
+            mem::size_of::<libbt::sockaddr_iso>()
+        }
+    };
+
+    (storage, len as btc::socklen_t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn security_error_kinds() {
+        let cases = [
+            (libc::EACCES, io::ErrorKind::PermissionDenied),
+            (libc::EKEYREJECTED, io::ErrorKind::PermissionDenied),
+            (libc::EKEYEXPIRED, io::ErrorKind::PermissionDenied),
+            (libc::EKEYREVOKED, io::ErrorKind::PermissionDenied),
+            (libc::ECONNREFUSED, io::ErrorKind::ConnectionRefused),
+            (libc::ETIMEDOUT, io::ErrorKind::TimedOut),
+        ];
+        for &(errno, kind) in &cases {
+            let err = security_error(io::Error::from_raw_os_error(errno));
+            assert_eq!(err.kind(), kind, "errno {}", errno);
+
+            let raw = err.raw_os_error().or_else(|| {
+                err.get_ref()
+                    .and_then(|inner| inner.downcast_ref::<io::Error>())
+                    .and_then(io::Error::raw_os_error)
+            });
+            assert_eq!(raw, Some(errno));
+        }
+    }
+
+    fn round_trip(addr: &BtSocketAddr, protocol: BtProtocol) -> BtSocketAddr {
+        let (storage, len) = addr_to_sockaddr(addr, protocol);
+        assert_eq!(storage.ss_family, libc::AF_BLUETOOTH as libc::sa_family_t);
+        assert!(len as usize <= mem::size_of::<btc::sockaddr_storage>());
+        sockaddr_to_addr(&storage, protocol)
+    }
+
+    #[test]
+    fn sockaddr_round_trip() {
+        let bdaddr = BtAddr([0x66, 0x55, 0x44, 0x33, 0x22, 0x11]);
+
+        let mut l2cap = BtSocketAddr::l2cap(bdaddr, 0x1001);
+        l2cap.set_cid(0x0040);
+        let mut iso = BtSocketAddr::new(bdaddr);
+        iso.set_addr_type(BtAddrType::LeRandom);
+
+        let cases = [
+            (BtSocketAddr::rfcomm(bdaddr, 3), BtProtocol::RFCOMM),
+            (l2cap, BtProtocol::L2CAP),
+            (
+                BtSocketAddr::l2cap_le(bdaddr, BtAddrType::LePublic, 0x0080),
+                BtProtocol::L2CAP,
+            ),
+            (
+                BtSocketAddr::l2cap_le(bdaddr, BtAddrType::LeRandom, 0x0081),
+                BtProtocol::L2CAP,
+            ),
+            (BtSocketAddr::new(bdaddr), BtProtocol::SCO),
+            (iso, BtProtocol::ISO),
+        ];
+        for (addr, protocol) in &cases {
+            assert_eq!(round_trip(addr, *protocol), *addr, "{:?}", protocol);
+        }
+    }
 
-        let sarc: &mut libbt::sockaddr_rc = unsafe { &mut *(&mut addr as *mut _ as *mut _) };
-        sarc.rc_family = libc::AF_BLUETOOTH as u16;
-        sarc.rc_bdaddr.b = self.0;
-        sarc.rc_channel = 1;
+    #[test]
+    fn sockaddr_l2_layout() {
+        let mut addr = BtSocketAddr::l2cap_le(
+            BtAddr([0x66, 0x55, 0x44, 0x33, 0x22, 0x11]),
+            BtAddrType::LeRandom,
+            0x1001,
+        );
+        addr.set_cid(0x0040);
+        let (storage, len) = addr_to_sockaddr(&addr, BtProtocol::L2CAP);
+        assert_eq!(len as usize, mem::size_of::<libbt::sockaddr_l2>());
 
-        (addr, mem::size_of::<libbt::sockaddr_rc>() as btc::socklen_t)
+        let sal2: &libbt::sockaddr_l2 = unsafe { &*(&storage as *const _ as *const _) };
+        // The kernel expects the PSM and CID in little-endian byte order.
+        assert_eq!(sal2.l2_psm.to_ne_bytes(), [0x01, 0x10]);
+        assert_eq!(sal2.l2_cid.to_ne_bytes(), [0x40, 0x00]);
+        assert_eq!(sal2.l2_bdaddr.b, [0x66, 0x55, 0x44, 0x33, 0x22, 0x11]);
+        assert_eq!(c_int::from(sal2.l2_bdaddr_type), libbt::BDADDR_LE_RANDOM);
     }
 }
@@ -1,6 +1,6 @@
 use std::cmp;
-use std::io;
-use std::mem::{self, MaybeUninit};
+use std::io::{self, IoSlice, IoSliceMut};
+use std::mem;
 use std::net::{self, Shutdown};
 use std::os::raw::{c_char, c_int, c_long, c_ulong};
 use std::ptr;
@@ -11,7 +11,7 @@ use crate::sys::{self, c};
 use crate::sys_common::bt;
 use crate::sys_common::{AsInner, FromInner, IntoInner};
 
-use crate::bt::{BtAddr, BtProtocol};
+use crate::bt::{BtAddr, BtProtocol, BtSocketAddr, SecurityLevel};
 
 pub mod btc {
     pub use crate::sys::c::SOCKADDR as sockaddr;
@@ -75,7 +75,7 @@ where
 }
 
 impl Socket {
-    pub fn new(protocol: BtProtocol) -> io::Result<Self> {
+    pub fn new(protocol: BtProtocol, ty: c_int) -> io::Result<Self> {
         init();
 
         let protocol = match protocol {
@@ -86,11 +86,23 @@ impl Socket {
                 ))
             } //c::BTHPROTO_L2CAP,
             BtProtocol::RFCOMM => c::BTHPROTO_RFCOMM,
+            BtProtocol::SCO => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "SCO is currently not supported on Windows",
+                ))
+            }
+            BtProtocol::ISO => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "ISO is currently not supported on Windows",
+                ))
+            }
         };
         let socket = unsafe {
             match c::WSASocketW(
                 c::AF_BTH as c_int,
-                c::SOCK_STREAM,
+                ty,
                 protocol as c_int,
                 ptr::null_mut(),
                 0,
@@ -104,7 +116,7 @@ impl Socket {
         Ok(socket)
     }
 
-    pub fn accept(&self) -> io::Result<(Socket, BtAddr)> {
+    pub fn accept(&self, protocol: BtProtocol) -> io::Result<(Socket, BtSocketAddr)> {
         let mut addr_storage = c::SOCKADDR_STORAGE_LH::default();
         let mut len = mem::size_of::<c::SOCKADDR_BTH>() as c_int;
 
@@ -116,40 +128,19 @@ impl Socket {
         }?;
         socket.set_no_inherit()?;
 
-        let addr = unsafe { &*(&addr_storage as *const _ as *const c::SOCKADDR_BTH) };
-        Ok((
-            socket,
-            BtAddr::nap_sap(c::GET_NAP(addr.btAddr), c::GET_SAP(addr.btAddr)),
-        ))
+        Ok((socket, sockaddr_to_addr(&addr_storage, protocol)))
     }
 
-    pub fn connect_timeout(&self, addr: &BtAddr, timeout: Duration) -> io::Result<()> {
+    pub fn connect_timeout(
+        &self,
+        addr: &BtSocketAddr,
+        protocol: BtProtocol,
+        timeout: Duration,
+    ) -> io::Result<()> {
         self.set_nonblocking(true)?;
         let r = {
-            let addr = {
-                fn init_bt_addr(storage: *mut c::SOCKADDR_STORAGE_LH, addr: &BtAddr) {
-                    let storage = storage as *mut c::SOCKADDR_BTH;
-                    unsafe {
-                        *storage = c::SOCKADDR_BTH {
-                            addressFamily: c::AF_BTH,
-                            btAddr: addr.into(),
-                            // serviceClassId: protocol_guid(self.protocol),
-                            ..Default::default()
-                        };
-                    }
-                }
-                let mut storage = MaybeUninit::<c::SOCKADDR_STORAGE_LH>::uninit();
-                init_bt_addr(storage.as_mut_ptr(), addr);
-                unsafe { storage.assume_init() }
-            };
-
-            cvt(unsafe {
-                c::connect(
-                    self.0,
-                    &addr as *const _ as *const c::SOCKADDR,
-                    mem::size_of::<c::SOCKADDR_BTH>() as i32,
-                )
-            })
+            let (addr, len) = addr_to_sockaddr(addr, protocol);
+            cvt(unsafe { c::connect(self.0, &addr as *const _ as *const c::SOCKADDR, len) })
         };
         self.set_nonblocking(false)?;
 
@@ -204,6 +195,27 @@ impl Socket {
         }
     }
 
+    /// Returns the protocol of a socket that was created elsewhere, checking that it is a
+    /// Bluetooth socket of a supported protocol.
+    pub fn protocol(&self) -> io::Result<BtProtocol> {
+        let info: c::WSAPROTOCOL_INFOW = bt::getsockopt(self, c::SOL_SOCKET, c::SO_PROTOCOL_INFOW)?;
+        if info.iAddressFamily != c::AF_BTH as c_int {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not a Bluetooth socket",
+            ));
+        }
+
+        if info.iProtocol == c::BTHPROTO_RFCOMM as c_int {
+            Ok(BtProtocol::RFCOMM)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "unsupported Bluetooth protocol",
+            ))
+        }
+    }
+
     pub fn duplicate(&self) -> io::Result<Socket> {
         let socket = {
             let mut info = c::WSAPROTOCOL_INFOW::default();
@@ -226,15 +238,28 @@ impl Socket {
         Ok(socket)
     }
 
-    pub fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, BtAddr)> {
-        self.recv_from_with_flags(buf, c::MSG_PEEK)
+    pub fn peek_from(
+        &self,
+        buf: &mut [u8],
+        protocol: BtProtocol,
+    ) -> io::Result<(usize, BtSocketAddr)> {
+        self.recv_from_with_flags(buf, protocol, c::MSG_PEEK)
     }
 
-    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, BtAddr)> {
-        self.recv_from_with_flags(buf, 0)
+    pub fn recv_from(
+        &self,
+        buf: &mut [u8],
+        protocol: BtProtocol,
+    ) -> io::Result<(usize, BtSocketAddr)> {
+        self.recv_from_with_flags(buf, protocol, 0)
     }
 
-    fn recv_from_with_flags(&self, buf: &mut [u8], flags: c_int) -> io::Result<(usize, BtAddr)> {
+    fn recv_from_with_flags(
+        &self,
+        buf: &mut [u8],
+        protocol: BtProtocol,
+        flags: c_int,
+    ) -> io::Result<(usize, BtSocketAddr)> {
         let mut addr_storage = c::SOCKADDR_STORAGE_LH::default();
         let mut addrlen = mem::size_of::<c::SOCKADDR_BTH>() as c_int;
         let len = cmp::min(buf.len(), <c_int>::max_value() as usize) as c_int;
@@ -250,20 +275,10 @@ impl Socket {
             )
         } {
             -1 if unsafe { c::WSAGetLastError() } == c::WSAESHUTDOWN => {
-                let addr = unsafe { &*(&addr_storage as *const _ as *const c::SOCKADDR_BTH) };
-                Ok((
-                    0,
-                    BtAddr::nap_sap(c::GET_NAP(addr.btAddr), c::GET_SAP(addr.btAddr)),
-                ))
+                Ok((0, sockaddr_to_addr(&addr_storage, protocol)))
             }
             -1 => Err(last_error()),
-            n => {
-                let addr = unsafe { &*(&addr_storage as *const _ as *const c::SOCKADDR_BTH) };
-                Ok((
-                    n as usize,
-                    BtAddr::nap_sap(c::GET_NAP(addr.btAddr), c::GET_SAP(addr.btAddr)),
-                ))
-            }
+            n => Ok((n as usize, sockaddr_to_addr(&addr_storage, protocol))),
         }
     }
 
@@ -275,6 +290,48 @@ impl Socket {
         self.recv_with_flags(buf, 0)
     }
 
+    pub fn read_vectored(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        // `IoSliceMut` is guaranteed to be ABI compatible with `WSABUF` on Windows.
+        let len = cmp::min(bufs.len(), c::DWORD::max_value() as usize) as c::DWORD;
+        let mut nread = 0;
+        let mut flags = 0;
+        match unsafe {
+            c::WSARecv(
+                self.0,
+                bufs.as_mut_ptr() as *mut c::WSABUF,
+                len,
+                &mut nread,
+                &mut flags,
+                ptr::null_mut(),
+                None,
+            )
+        } {
+            0 => Ok(nread as usize),
+            _ if unsafe { c::WSAGetLastError() } == c::WSAESHUTDOWN => Ok(0),
+            _ => Err(last_error()),
+        }
+    }
+
+    pub fn send_vectored(&self, bufs: &[IoSlice<'_>], flags: c_int) -> io::Result<usize> {
+        // `IoSlice` is guaranteed to be ABI compatible with `WSABUF` on Windows.
+        let len = cmp::min(bufs.len(), c::DWORD::max_value() as usize) as c::DWORD;
+        let mut nwritten = 0;
+        match unsafe {
+            c::WSASend(
+                self.0,
+                bufs.as_ptr() as *const c::WSABUF as *mut c::WSABUF,
+                len,
+                &mut nwritten,
+                flags as c::DWORD,
+                ptr::null_mut(),
+                None,
+            )
+        } {
+            0 => Ok(nwritten as usize),
+            _ => Err(last_error()),
+        }
+    }
+
     fn recv_with_flags(&self, buf: &mut [u8], flags: c_int) -> io::Result<usize> {
         // On unix when a socket is shut down all further reads return 0, so we
         // do the same on windows to map a shut down to return EOF.
@@ -286,6 +343,67 @@ impl Socket {
         }
     }
 
+    pub fn peek_trunc(&self, buf: &mut [u8]) -> io::Result<(usize, bool)> {
+        self.recv_trunc_with_flags(buf, c::MSG_PEEK)
+    }
+
+    pub fn recv_trunc(&self, buf: &mut [u8]) -> io::Result<(usize, bool)> {
+        self.recv_trunc_with_flags(buf, 0)
+    }
+
+    fn recv_trunc_with_flags(&self, buf: &mut [u8], flags: c_int) -> io::Result<(usize, bool)> {
+        // Windows fills the buffer and reports `WSAEMSGSIZE` when a message was truncated.
+        let len = cmp::min(buf.len(), <c_int>::max_value() as usize) as c_int;
+        match unsafe { c::recv(self.0, buf.as_mut_ptr() as *mut c_char, len, flags) } {
+            -1 => match unsafe { c::WSAGetLastError() } {
+                c::WSAESHUTDOWN => Ok((0, false)),
+                c::WSAEMSGSIZE => Ok((len as usize, true)),
+                _ => Err(last_error()),
+            },
+            n => Ok((n as usize, false)),
+        }
+    }
+
+    pub fn set_security(&self, level: SecurityLevel) -> io::Result<()> {
+        // Windows only allows requiring authentication and encryption; neither can be
+        // turned off once required.
+        if level >= SecurityLevel::Medium {
+            bt::setsockopt(
+                self,
+                c::SOL_RFCOMM as c_int,
+                c::SO_BTH_AUTHENTICATE as c_int,
+                c::TRUE,
+            )?;
+        }
+        if level >= SecurityLevel::High {
+            bt::setsockopt(
+                self,
+                c::SOL_RFCOMM as c_int,
+                c::SO_BTH_ENCRYPT as c_int,
+                c::TRUE,
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn security(&self) -> io::Result<(SecurityLevel, u8)> {
+        let encrypt: c::BOOL =
+            bt::getsockopt(self, c::SOL_RFCOMM as c_int, c::SO_BTH_ENCRYPT as c_int)?;
+        if encrypt != c::FALSE {
+            return Ok((SecurityLevel::High, 0));
+        }
+        let authenticate: c::BOOL = bt::getsockopt(
+            self,
+            c::SOL_RFCOMM as c_int,
+            c::SO_BTH_AUTHENTICATE as c_int,
+        )?;
+        if authenticate != c::FALSE {
+            Ok((SecurityLevel::Medium, 0))
+        } else {
+            Ok((SecurityLevel::Low, 0))
+        }
+    }
+
     pub fn set_timeout(&self, dur: Option<Duration>, kind: c_int) -> io::Result<()> {
         let timeout = match dur {
             Some(dur) => {
@@ -373,22 +491,52 @@ impl IntoInner<c::SOCKET> for Socket {
     }
 }
 
-pub fn discover_devices() -> io::Result<Vec<BtAddr>> {
+/// The address, raw class of device, page scan repetition mode and clock offset of a
+/// device that responded to an inquiry.
+pub type InquiryResult = (BtAddr, [u8; 3], Option<u8>, Option<u16>);
+
+/// Runs an inquiry on all local radios, returning each device that responded. The page
+/// scan repetition mode and clock offset of devices are not reported on Windows.
+///
+/// `length` is in units of 1.28 seconds, and a `max_responses` of 0 means no limit.
+pub fn inquiry(
+    adapter: Option<&BtAddr>,
+    length: u8,
+    max_responses: u8,
+    flush_cache: bool,
+    lap: [u8; 3],
+) -> io::Result<Vec<InquiryResult>> {
+    if adapter.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Selecting the adapter of an inquiry is currently not supported on Windows",
+        ));
+    }
     init();
 
     let handle: c::HANDLE = {
+        let mut device_query = c::BTH_QUERY_DEVICE {
+            LAP: u32::from_le_bytes([lap[0], lap[1], lap[2], 0]),
+            length,
+        };
+        let mut blob = c::BLOB {
+            cbSize: mem::size_of::<c::BTH_QUERY_DEVICE>() as u32,
+            pBlobData: &mut device_query as *mut _ as *mut u8,
+        };
         let mut query: c::WSAQUERYSETW = Default::default();
         query.dwSize = mem::size_of::<c::WSAQUERYSETW>() as u32;
         query.dwNameSpace = c::NS_BTH;
+        query.lpBlob = &mut blob;
 
+        // Without flushing the cache, no inquiry is made, and the devices found by
+        // previous inquiries are returned.
+        let flags = if flush_cache {
+            c::LUP_CONTAINERS | c::LUP_FLUSHCACHE
+        } else {
+            c::LUP_CONTAINERS
+        };
         let mut handle: c::HANDLE = std::ptr::null_mut();
-        if 0 != unsafe {
-            c::WSALookupServiceBeginW(
-                &mut query,
-                c::LUP_CONTAINERS | c::LUP_FLUSHCACHE,
-                &mut handle,
-            )
-        } {
+        if 0 != unsafe { c::WSALookupServiceBeginW(&mut query, flags, &mut handle) } {
             Err(last_error())
         } else {
             Ok(handle)
@@ -399,8 +547,8 @@ pub fn discover_devices() -> io::Result<Vec<BtAddr>> {
     let mut buffer: Vec<usize> =
         vec![0; mem::size_of::<c::WSAQUERYSETW>() / mem::size_of::<usize>()];
 
-    let mut addresses = Vec::new();
-    loop {
+    let mut devices = Vec::new();
+    while max_responses == 0 || devices.len() < usize::from(max_responses) {
         let (query, mut len) = {
             let slice = &mut buffer[..];
             (
@@ -412,16 +560,20 @@ pub fn discover_devices() -> io::Result<Vec<BtAddr>> {
         unsafe {
             if 0 == c::WSALookupServiceNextW(
                 handle,
-                c::LUP_CONTAINERS | c::LUP_RETURN_ADDR,
+                c::LUP_CONTAINERS | c::LUP_RETURN_ADDR | c::LUP_RETURN_BLOB,
                 &mut len,
                 query,
             ) {
                 let query: c::WSAQUERYSETW = *query;
                 let addr_info: c::CSADDR_INFO = *query.lpcsaBuffer;
                 let addr = *(addr_info.RemoteAddr.lpSockaddr as *mut c::SOCKADDR_BTH);
-                addresses.push(BtAddr::nap_sap(
-                    c::GET_NAP(addr.btAddr),
-                    c::GET_SAP(addr.btAddr),
+                let info = *((*query.lpBlob).pBlobData as *mut c::BTH_DEVICE_INFO);
+                let class = info.classOfDevice.to_le_bytes();
+                devices.push((
+                    BtAddr::nap_sap(c::GET_NAP(addr.btAddr), c::GET_SAP(addr.btAddr)),
+                    [class[0], class[1], class[2]],
+                    None,
+                    None,
                 ));
             } else {
                 let err = last_error();
@@ -440,14 +592,41 @@ pub fn discover_devices() -> io::Result<Vec<BtAddr>> {
     if 0 != unsafe { c::WSALookupServiceEnd(handle) } {
         Err(last_error())
     } else {
-        Ok(addresses)
+        Ok(devices)
     }
 }
 
+// Authentication failures are reported as `WSAEACCES` and refused connections as
+// `WSAECONNREFUSED`, which already map to `PermissionDenied` and `ConnectionRefused`.
+pub fn security_error(err: io::Error) -> io::Error {
+    err
+}
+
+/// Returns whether `err` reports that the link to the remote device was lost.
+pub fn is_link_loss(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::TimedOut
+    )
+}
+
+/// Returns whether a connection attempt failed with `err` for a reason that may go away,
+/// such as the remote device being out of range or busy.
+pub fn is_transient_connect_error(err: &io::Error) -> bool {
+    is_link_loss(err) || err.kind() == io::ErrorKind::ConnectionRefused
+}
+
 fn protocol_guid(protocol: BtProtocol) -> c::GUID {
     match protocol {
         BtProtocol::L2CAP => c::L2CAP_PROTOCOL_UUID,
         BtProtocol::RFCOMM => c::RFCOMM_PROTOCOL_UUID,
+        // SCO and ISO sockets cannot be created on Windows, so the service class is
+        // irrelevant.
+        BtProtocol::SCO | BtProtocol::ISO => c::GUID::default(),
     }
 }
 
@@ -459,24 +638,29 @@ impl<'a> Into<u64> for &'a BtAddr {
     }
 }
 
-impl<'a> Into<BtAddr> for &'a btc::sockaddr_storage {
-    fn into(self) -> BtAddr {
-        let sab: &'a c::SOCKADDR_BTH = unsafe { &*(self as *const _ as *const _) };
-        BtAddr::nap_sap(c::GET_NAP(sab.btAddr), c::GET_SAP(sab.btAddr))
-    }
+pub fn sockaddr_to_addr(storage: &btc::sockaddr_storage, _protocol: BtProtocol) -> BtSocketAddr {
+    let sab: &c::SOCKADDR_BTH = unsafe { &*(storage as *const _ as *const _) };
+    let addr = BtAddr::nap_sap(c::GET_NAP(sab.btAddr), c::GET_SAP(sab.btAddr));
+    BtSocketAddr::rfcomm(addr, sab.port as u8)
 }
 
-impl<'a> Into<(btc::sockaddr_storage, btc::socklen_t)> for &'a BtAddr {
-    fn into(self) -> (btc::sockaddr_storage, btc::socklen_t) {
-        let mut addr = btc::sockaddr_storage {
-            ss_family: c::AF_BTH,
-            ..Default::default()
-        };
-
-        let sab: &mut c::SOCKADDR_BTH = unsafe { &mut *(&mut addr as *mut _ as *mut _) };
-        sab.btAddr = self.into();
-        sab.serviceClassId = c::RFCOMM_PROTOCOL_UUID;
-
-        (addr, mem::size_of::<c::SOCKADDR_BTH>() as c_int)
-    }
+pub fn addr_to_sockaddr(
+    addr: &BtSocketAddr,
+    protocol: BtProtocol,
+) -> (btc::sockaddr_storage, btc::socklen_t) {
+    let mut storage = btc::sockaddr_storage {
+        ss_family: c::AF_BTH,
+        ..Default::default()
+    };
+
+    let sab: &mut c::SOCKADDR_BTH = unsafe { &mut *(&mut storage as *mut _ as *mut _) };
+    sab.btAddr = addr.addr().into();
+    sab.serviceClassId = protocol_guid(protocol);
+    // Windows uses `BT_PORT_ANY` rather than 0 to let the OS assign a channel.
+    sab.port = match addr.channel() {
+        0 => c::BT_PORT_ANY as c_ulong,
+        channel => c_ulong::from(channel),
+    };
+
+    (storage, mem::size_of::<c::SOCKADDR_BTH>() as c_int)
 }
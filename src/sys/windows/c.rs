@@ -1,9 +1,11 @@
 pub use winapi::shared::bthdef::{
-    GET_NAP, GET_SAP, L2CAP_PROTOCOL_UUID, RFCOMM_PROTOCOL_UUID, SET_NAP_SAP,
+    BTH_DEVICE_INFO, GET_NAP, GET_SAP, L2CAP_PROTOCOL_UUID, RFCOMM_PROTOCOL_UUID, SET_NAP_SAP,
 };
 pub use winapi::shared::guiddef::GUID;
+pub use winapi::shared::minwindef::{BOOL, DWORD, FALSE, TRUE};
 pub use winapi::shared::winerror::{WSAEFAULT, WSA_E_NO_MORE};
-pub use winapi::shared::ws2def::{CSADDR_INFO, SOCKADDR, SOCKADDR_STORAGE_LH};
+pub use winapi::shared::ws2def::{CSADDR_INFO, SOCKADDR, SOCKADDR_STORAGE_LH, WSABUF};
+pub use winapi::shared::wtypesbase::BLOB;
 pub use winapi::um::handleapi::SetHandleInformation;
 pub use winapi::um::processthreadsapi::GetCurrentProcessId;
 pub use winapi::um::winbase::{HANDLE_FLAG_INHERIT, INFINITE};
@@ -12,9 +14,13 @@ pub use winapi::um::winsock2::{
     accept, bind, closesocket, connect, fd_set, getpeername, getsockname, getsockopt, ioctlsocket,
     listen, recv, recvfrom, select, send, sendto, setsockopt, shutdown, timeval, WSACleanup,
     WSADuplicateSocketW, WSAGetLastError, WSALookupServiceBeginW, WSALookupServiceEnd,
-    WSALookupServiceNextW, WSASocketW, WSAStartup, FIONBIO, INVALID_SOCKET, LUP_CONTAINERS,
-    LUP_FLUSHCACHE, LUP_RETURN_ADDR, MSG_PEEK, NS_BTH, SD_BOTH, SD_RECEIVE, SD_SEND, SOCKET,
-    SOCKET_ERROR, SOCK_STREAM, SOL_SOCKET, SO_ERROR, SO_RCVTIMEO, SO_REUSEADDR, SO_SNDTIMEO,
-    WSADATA, WSAESHUTDOWN, WSAPROTOCOL_INFOW, WSAQUERYSETW, WSA_FLAG_OVERLAPPED,
+    WSALookupServiceNextW, WSARecv, WSASend, WSASocketW, WSAStartup, FIONBIO, INVALID_SOCKET,
+    LUP_CONTAINERS, LUP_FLUSHCACHE, LUP_RETURN_ADDR, LUP_RETURN_BLOB, MSG_PEEK, NS_BTH, SD_BOTH,
+    SD_RECEIVE, SD_SEND, SOCKET, SOCKET_ERROR, SOCK_DGRAM, SOCK_SEQPACKET, SOCK_STREAM, SOL_SOCKET,
+    SO_ERROR, SO_PROTOCOL_INFOW, SO_RCVTIMEO, SO_REUSEADDR, SO_SNDTIMEO, WSADATA, WSAEMSGSIZE,
+    WSAESHUTDOWN, WSAPROTOCOL_INFOW, WSAQUERYSETW, WSA_FLAG_OVERLAPPED,
+};
+pub use winapi::um::ws2bth::{
+    AF_BTH, BTHPROTO_L2CAP, BTHPROTO_RFCOMM, BTH_QUERY_DEVICE, BT_PORT_ANY, SOCKADDR_BTH,
+    SOL_RFCOMM, SO_BTH_AUTHENTICATE, SO_BTH_ENCRYPT,
 };
-pub use winapi::um::ws2bth::{AF_BTH, BTHPROTO_L2CAP, BTHPROTO_RFCOMM, BT_PORT_ANY, SOCKADDR_BTH};
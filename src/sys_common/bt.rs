@@ -1,6 +1,6 @@
 use std::cmp;
 use std::fmt;
-use std::io;
+use std::io::{self, IoSlice, IoSliceMut};
 use std::mem;
 use std::net::Shutdown;
 use std::os::raw::c_int;
@@ -8,10 +8,11 @@ use std::time::Duration;
 
 use crate::sys::bt::btc as c;
 use crate::sys::bt::Socket;
+use crate::sys::bt::{addr_to_sockaddr, sockaddr_to_addr};
 use crate::sys::bt::{cvt, cvt_r};
 use crate::sys_common::AsInner;
 
-use crate::bt::{BtAddr, BtProtocol};
+use crate::bt::{BtProtocol, BtSocketAddr};
 
 cfg_if! {
     if #[cfg(any(
@@ -60,14 +61,52 @@ pub fn getsockopt<T: Copy>(sock: &Socket, opt: c_int, val: c_int) -> io::Result<
     }
 }
 
-fn sockname<F>(f: F) -> io::Result<BtAddr>
+fn sockname<F>(f: F, protocol: BtProtocol) -> io::Result<BtSocketAddr>
 where
     F: FnOnce(*mut c::sockaddr_storage, *mut c::socklen_t) -> c_int,
 {
     let mut addr: c::sockaddr_storage = unsafe { mem::zeroed() };
     let mut len = mem::size_of_val(&addr) as c::socklen_t;
     cvt(f(&mut addr, &mut len))?;
-    Ok((&addr).into())
+    Ok(sockaddr_to_addr(&addr, protocol))
+}
+
+pub fn bind(sock: &Socket, addr: &BtSocketAddr, protocol: BtProtocol) -> io::Result<()> {
+    let (addr, len) = addr_to_sockaddr(addr, protocol);
+    cvt(unsafe { c::bind(*sock.as_inner(), &addr as *const _ as *const _, len) })?;
+    Ok(())
+}
+
+fn send(sock: &Socket, buf: &[u8]) -> io::Result<usize> {
+    cvt(unsafe {
+        c::send(
+            *sock.as_inner(),
+            buf.as_ptr() as *const _,
+            cmp::min(buf.len(), <c::wrlen_t>::max_value() as usize) as c::wrlen_t,
+            MSG_NOSIGNAL,
+        )
+    })
+    .map(|ret| ret as usize)
+}
+
+fn send_to(
+    sock: &Socket,
+    buf: &[u8],
+    dst: &BtSocketAddr,
+    protocol: BtProtocol,
+) -> io::Result<usize> {
+    let (addr, addrlen) = addr_to_sockaddr(dst, protocol);
+    cvt(unsafe {
+        c::sendto(
+            *sock.as_inner(),
+            buf.as_ptr() as *const _,
+            cmp::min(buf.len(), <c::wrlen_t>::max_value() as usize) as c::wrlen_t,
+            MSG_NOSIGNAL,
+            &addr as *const _ as *const _,
+            addrlen,
+        )
+    })
+    .map(|ret| ret as usize)
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -80,27 +119,81 @@ pub struct BtListener {
 }
 
 impl BtListener {
-    pub fn bind(addr: &BtAddr, protocol: BtProtocol) -> io::Result<Self> {
-        let socket = Socket::new(protocol)?;
+    /// Adopts a socket that was created elsewhere, which must be a Bluetooth socket of a
+    /// supported protocol.
+    pub fn from_socket(socket: Socket) -> io::Result<Self> {
+        let protocol = socket.protocol()?;
+        Ok(Self {
+            inner: socket,
+            protocol,
+        })
+    }
+
+    pub fn bind(addr: &BtSocketAddr, protocol: BtProtocol, ty: c_int) -> io::Result<Self> {
+        Self::bind_with(addr, protocol, ty, |_| Ok(()))
+    }
+
+    /// Like `bind`, but calls `setup` on the socket after it is bound and before it starts
+    /// listening.
+    ///
+    /// Some options, such as the mode of L2CAP sockets, are only accepted once the socket
+    /// is bound. The others that listeners set, the L2CAP receive MTU, SCO voice setting
+    /// and ISO QoS, are accepted both before and after binding.
+    pub fn bind_with<F>(
+        addr: &BtSocketAddr,
+        protocol: BtProtocol,
+        ty: c_int,
+        setup: F,
+    ) -> io::Result<Self>
+    where
+        F: FnOnce(&Socket) -> io::Result<()>,
+    {
+        Self::listen_with(protocol, ty, |socket| {
+            bind(socket, addr, protocol)?;
+            setup(socket)
+        })
+    }
+
+    /// Creates a listener whose socket is bound by `bind_setup` rather than from a
+    /// `BtSocketAddr`, for addresses that need a protocol-specific layout.
+    pub fn listen_with<F>(protocol: BtProtocol, ty: c_int, bind_setup: F) -> io::Result<Self>
+    where
+        F: FnOnce(&Socket) -> io::Result<()>,
+    {
+        Self::listen_with_options(protocol, ty, 128, true, bind_setup)
+    }
+
+    /// Like `listen_with`, but with the given `backlog` and only setting `SO_REUSEADDR`
+    /// if `reuse_addr` is true.
+    pub fn listen_with_options<F>(
+        protocol: BtProtocol,
+        ty: c_int,
+        backlog: c_int,
+        reuse_addr: bool,
+        bind_setup: F,
+    ) -> io::Result<Self>
+    where
+        F: FnOnce(&Socket) -> io::Result<()>,
+    {
+        let socket = Socket::new(protocol, ty)?;
 
         // On platforms with Berkeley-derived sockets, this allows
         // to quickly rebind a socket, without needing to wait for
         // the OS to clean up the previous one.
-        if !cfg!(windows) {
+        if reuse_addr && !cfg!(windows) {
             setsockopt(&socket, c::SOL_SOCKET, c::SO_REUSEADDR, 1 as c_int)?;
         }
 
-        let (addr, len) = addr.into();
-        cvt(unsafe { c::bind(*socket.as_inner(), &addr as *const _ as *const _, len) })?;
-        cvt(unsafe { c::listen(*socket.as_inner(), 128) })?;
+        bind_setup(&socket)?;
+        cvt(unsafe { c::listen(*socket.as_inner(), backlog) })?;
         Ok(Self {
             inner: socket,
             protocol,
         })
     }
 
-    pub fn accept(&self) -> io::Result<(BtStream, BtAddr)> {
-        self.inner.accept().map(|(socket, addr)| {
+    pub fn accept(&self) -> io::Result<(BtStream, BtSocketAddr)> {
+        self.inner.accept(self.protocol).map(|(socket, addr)| {
             (
                 BtStream {
                     inner: socket,
@@ -115,8 +208,11 @@ impl BtListener {
         self.inner.set_nonblocking(nonblocking)
     }
 
-    pub fn local_addr(&self) -> io::Result<BtAddr> {
-        sockname(|addr, len| unsafe { c::getsockname(*self.inner.as_inner(), addr as *mut _, len) })
+    pub fn local_addr(&self) -> io::Result<BtSocketAddr> {
+        sockname(
+            |addr, len| unsafe { c::getsockname(*self.inner.as_inner(), addr as *mut _, len) },
+            self.protocol,
+        )
     }
 
     pub fn take_error(&self) -> io::Result<Option<io::Error>> {
@@ -166,10 +262,34 @@ pub struct BtStream {
 }
 
 impl BtStream {
-    pub fn connect(addr: &BtAddr, protocol: BtProtocol) -> io::Result<Self> {
-        let (addr, len) = addr.into();
+    /// Adopts a socket that was created elsewhere, which must be a Bluetooth socket of a
+    /// supported protocol.
+    pub fn from_socket(socket: Socket) -> io::Result<Self> {
+        let protocol = socket.protocol()?;
+        Ok(Self {
+            inner: socket,
+            protocol,
+        })
+    }
 
-        let socket = Socket::new(protocol)?;
+    pub fn connect(addr: &BtSocketAddr, protocol: BtProtocol, ty: c_int) -> io::Result<Self> {
+        Self::connect_with(addr, protocol, ty, |_| Ok(()))
+    }
+
+    /// Like `connect`, but calls `setup` on the socket before it is connected.
+    pub fn connect_with<F>(
+        addr: &BtSocketAddr,
+        protocol: BtProtocol,
+        ty: c_int,
+        setup: F,
+    ) -> io::Result<Self>
+    where
+        F: FnOnce(&Socket) -> io::Result<()>,
+    {
+        let (addr, len) = addr_to_sockaddr(addr, protocol);
+
+        let socket = Socket::new(protocol, ty)?;
+        setup(&socket)?;
         cvt_r(|| unsafe { c::connect(*socket.as_inner(), &addr as *const _ as *const _, len) })?;
         Ok(Self {
             inner: socket,
@@ -178,59 +298,95 @@ impl BtStream {
     }
 
     pub fn connect_timeout(
-        addr: &BtAddr,
+        addr: &BtSocketAddr,
         protocol: BtProtocol,
+        ty: c_int,
         timeout: Duration,
     ) -> io::Result<Self> {
-        let socket = Socket::new(protocol)?;
-        socket.connect_timeout(addr, timeout)?;
+        Self::connect_timeout_with(addr, protocol, ty, timeout, |_| Ok(()))
+    }
+
+    /// Like `connect_timeout`, but calls `setup` on the socket before it is connected.
+    pub fn connect_timeout_with<F>(
+        addr: &BtSocketAddr,
+        protocol: BtProtocol,
+        ty: c_int,
+        timeout: Duration,
+        setup: F,
+    ) -> io::Result<Self>
+    where
+        F: FnOnce(&Socket) -> io::Result<()>,
+    {
+        let socket = Socket::new(protocol, ty)?;
+        setup(&socket)?;
+        socket.connect_timeout(addr, protocol, timeout)?;
         Ok(Self {
             inner: socket,
             protocol,
         })
     }
 
+    /// Creates a nonblocking socket and starts connecting it to `addr`.
+    ///
+    /// Returns the stream and whether it is already connected. Otherwise, the socket
+    /// becomes writable once the attempt completes, after which `take_error` reports
+    /// whether it failed.
+    #[cfg(all(unix, any(feature = "async-io", feature = "mio", feature = "tokio")))]
+    pub fn connect_nonblocking(
+        addr: &BtSocketAddr,
+        protocol: BtProtocol,
+        ty: c_int,
+    ) -> io::Result<(Self, bool)> {
+        let socket = Socket::new(protocol, ty)?;
+        socket.set_nonblocking(true)?;
+        let connected = socket.start_connect(addr, protocol)?;
+        Ok((
+            Self {
+                inner: socket,
+                protocol,
+            },
+            connected,
+        ))
+    }
+
     pub fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
         self.inner.peek(buf)
     }
 
-    pub fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, BtAddr)> {
-        self.inner.peek_from(buf)
+    pub fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, BtSocketAddr)> {
+        self.inner.peek_from(buf, self.protocol)
+    }
+
+    pub fn peek_trunc(&self, buf: &mut [u8]) -> io::Result<(usize, bool)> {
+        self.inner.peek_trunc(buf)
     }
 
     pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
         self.inner.read(buf)
     }
 
-    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, BtAddr)> {
-        self.inner.recv_from(buf)
+    pub fn recv_trunc(&self, buf: &mut [u8]) -> io::Result<(usize, bool)> {
+        self.inner.recv_trunc(buf)
+    }
+
+    pub fn recv_vectored(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        self.inner.read_vectored(bufs)
+    }
+
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, BtSocketAddr)> {
+        self.inner.recv_from(buf, self.protocol)
     }
 
     pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
-        cvt(unsafe {
-            c::send(
-                *self.inner.as_inner(),
-                buf.as_ptr() as *const _,
-                cmp::min(buf.len(), <c::wrlen_t>::max_value() as usize) as c::wrlen_t,
-                MSG_NOSIGNAL,
-            )
-        })
-        .map(|ret| ret as usize)
-    }
-
-    pub fn send_to(&self, buf: &[u8], dst: &BtAddr) -> io::Result<usize> {
-        let (addr, addrlen) = dst.into();
-        cvt(unsafe {
-            c::sendto(
-                *self.inner.as_inner(),
-                buf.as_ptr() as *const _,
-                cmp::min(buf.len(), <c::wrlen_t>::max_value() as usize) as c::wrlen_t,
-                MSG_NOSIGNAL,
-                &addr as *const _ as *const _,
-                addrlen,
-            )
-        })
-        .map(|ret| ret as usize)
+        send(&self.inner, buf)
+    }
+
+    pub fn send_vectored(&self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.inner.send_vectored(bufs, MSG_NOSIGNAL)
+    }
+
+    pub fn send_to(&self, buf: &[u8], dst: &BtSocketAddr) -> io::Result<usize> {
+        send_to(&self.inner, buf, dst, self.protocol)
     }
 
     pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
@@ -257,12 +413,18 @@ impl BtStream {
         self.inner.set_nonblocking(nonblocking)
     }
 
-    pub fn local_addr(&self) -> io::Result<BtAddr> {
-        sockname(|addr, len| unsafe { c::getsockname(*self.inner.as_inner(), addr as *mut _, len) })
+    pub fn local_addr(&self) -> io::Result<BtSocketAddr> {
+        sockname(
+            |addr, len| unsafe { c::getsockname(*self.inner.as_inner(), addr as *mut _, len) },
+            self.protocol,
+        )
     }
 
-    pub fn peer_addr(&self) -> io::Result<BtAddr> {
-        sockname(|addr, len| unsafe { c::getpeername(*self.inner.as_inner(), addr as *mut _, len) })
+    pub fn peer_addr(&self) -> io::Result<BtSocketAddr> {
+        sockname(
+            |addr, len| unsafe { c::getpeername(*self.inner.as_inner(), addr as *mut _, len) },
+            self.protocol,
+        )
     }
 
     pub fn take_error(&self) -> io::Result<Option<io::Error>> {
@@ -301,3 +463,188 @@ impl fmt::Debug for BtStream {
         res.field(name, &self.inner.as_inner()).finish()
     }
 }
+
+////////////////////////////////////////////////////////////////////////////////
+// Bluetooth datagrams
+////////////////////////////////////////////////////////////////////////////////
+
+pub struct BtDatagram {
+    inner: Socket,
+    protocol: BtProtocol,
+}
+
+impl BtDatagram {
+    pub fn bind(addr: &BtSocketAddr, protocol: BtProtocol) -> io::Result<Self> {
+        let socket = Socket::new(protocol, c::SOCK_DGRAM)?;
+        bind(&socket, addr, protocol)?;
+        Ok(Self {
+            inner: socket,
+            protocol,
+        })
+    }
+
+    pub fn connect(&self, addr: &BtSocketAddr) -> io::Result<()> {
+        let (addr, len) = addr_to_sockaddr(addr, self.protocol);
+        cvt_r(|| unsafe { c::connect(*self.inner.as_inner(), &addr as *const _ as *const _, len) })
+            .map(|_| ())
+    }
+
+    pub fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.peek(buf)
+    }
+
+    pub fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, BtSocketAddr)> {
+        self.inner.peek_from(buf, self.protocol)
+    }
+
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, BtSocketAddr)> {
+        self.inner.recv_from(buf, self.protocol)
+    }
+
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        send(&self.inner, buf)
+    }
+
+    pub fn send_to(&self, buf: &[u8], dst: &BtSocketAddr) -> io::Result<usize> {
+        send_to(&self.inner, buf, dst, self.protocol)
+    }
+
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.inner.timeout(c::SO_RCVTIMEO)
+    }
+
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.set_timeout(dur, c::SO_RCVTIMEO)
+    }
+
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.inner.timeout(c::SO_SNDTIMEO)
+    }
+
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.set_timeout(dur, c::SO_SNDTIMEO)
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.inner.set_nonblocking(nonblocking)
+    }
+
+    pub fn local_addr(&self) -> io::Result<BtSocketAddr> {
+        sockname(
+            |addr, len| unsafe { c::getsockname(*self.inner.as_inner(), addr as *mut _, len) },
+            self.protocol,
+        )
+    }
+
+    pub fn peer_addr(&self) -> io::Result<BtSocketAddr> {
+        sockname(
+            |addr, len| unsafe { c::getpeername(*self.inner.as_inner(), addr as *mut _, len) },
+            self.protocol,
+        )
+    }
+
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.inner.take_error()
+    }
+
+    pub fn duplicate(&self) -> io::Result<Self> {
+        self.inner.duplicate().map(|s| Self {
+            inner: s,
+            protocol: self.protocol,
+        })
+    }
+}
+
+impl fmt::Debug for BtDatagram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut res = f.debug_struct("BtDatagram");
+
+        if let Ok(addr) = self.local_addr() {
+            res.field("addr", &addr);
+        }
+
+        let name = if cfg!(windows) { "socket" } else { "fd" };
+        res.field(name, &self.inner.as_inner()).finish()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// HCI sockets
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(unix)]
+pub struct HciSocket {
+    inner: Socket,
+    dev_id: u16,
+}
+
+#[cfg(unix)]
+impl HciSocket {
+    pub fn open(dev_id: u16) -> io::Result<Self> {
+        Socket::new_hci(dev_id).map(|socket| Self {
+            inner: socket,
+            dev_id,
+        })
+    }
+
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        send(&self.inner, buf)
+    }
+
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.inner.timeout(c::SO_RCVTIMEO)
+    }
+
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.set_timeout(dur, c::SO_RCVTIMEO)
+    }
+
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.inner.timeout(c::SO_SNDTIMEO)
+    }
+
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.set_timeout(dur, c::SO_SNDTIMEO)
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.inner.set_nonblocking(nonblocking)
+    }
+
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.inner.take_error()
+    }
+
+    pub fn dev_id(&self) -> u16 {
+        self.dev_id
+    }
+
+    pub fn socket(&self) -> &Socket {
+        &self.inner
+    }
+
+    pub fn duplicate(&self) -> io::Result<Self> {
+        self.inner.duplicate().map(|s| Self {
+            inner: s,
+            dev_id: self.dev_id,
+        })
+    }
+}
+
+#[cfg(unix)]
+impl fmt::Debug for HciSocket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HciSocket")
+            .field("dev_id", &self.dev_id)
+            .field("fd", &self.inner.as_inner())
+            .finish()
+    }
+}